@@ -0,0 +1,188 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use my_lib::{
+    gallery::{self, GalleryEntry, Picture},
+    graph::{layout::LayoutHints, stats::GraphStats, traits::ReadGraph},
+    registry::Registry,
+};
+use petgraph::{Graph, Undirected};
+use rayon::prelude::*;
+
+use crate::{create_parent_dir, open_file, CanvasArgs, GraphType, RenderArgs};
+
+#[derive(clap::Args)]
+pub struct GalleryArgs {
+    /// Directory with graph files, searched recursively
+    dir: PathBuf,
+    /// Directory for the pictures and index.html
+    #[arg(short, long, default_value = "./tmp/gallery")]
+    output: PathBuf,
+    #[arg(short, long, value_enum, default_value_t = GraphType::List)]
+    graph: GraphType,
+    /// Registered node projectors, drawn with their default options
+    #[arg(short, long, value_delimiter = ',', default_values = ["circle", "spiral"])]
+    node_projectors: Vec<String>,
+    /// Registered drawing apis, drawn with their default options
+    #[arg(short, long, value_delimiter = ',', default_values = ["simple-svg", "draw"])]
+    draw_using: Vec<String>,
+    /// Don't open the gallery after drawing
+    #[arg(long)]
+    no_open: bool,
+    #[command(flatten)]
+    canvas: CanvasArgs,
+    #[command(flatten)]
+    render: RenderArgs,
+}
+
+/// Graph files under `dir`, sorted by path
+fn find_graphs(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files = vec![];
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Unable to read '{}': {}", dir.display(), e))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|e| format!("Unable to read '{}': {}", dir.display(), e))?
+            .path();
+        if path.is_dir() {
+            files.extend(find_graphs(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Renders one graph with every projector and api, pictures go into `output/name/`
+fn render_graph(
+    registry: &Registry,
+    extensions: &HashMap<&str, &str>,
+    args: &GalleryArgs,
+    file: &Path,
+    name: &str,
+) -> GalleryEntry {
+    let mut entry = GalleryEntry {
+        name: name.to_string(),
+        stats: None,
+        pictures: vec![],
+        errors: vec![],
+    };
+    let input = match fs::read_to_string(file) {
+        Ok(input) => input,
+        Err(e) => {
+            entry.errors.push(format!("Unable to read: {}", e));
+            return entry;
+        }
+    };
+    match Graph::<usize, (), Undirected, usize>::read_from_str(&input) {
+        Ok(graph) => entry.stats = Some(GraphStats::of(&graph)),
+        Err(e) => {
+            entry.errors.push(format!("Malformed graph: {}", e));
+            return entry;
+        }
+    }
+
+    let combinations = args
+        .node_projectors
+        .iter()
+        .flat_map(|np| args.draw_using.iter().map(move |api| (np, api)))
+        .collect::<Vec<_>>();
+    let results = combinations
+        .into_par_iter()
+        .map(|(np, api)| {
+            let path = format!("{}/{}-{}.{}", name, np, api, extensions[api.as_str()]);
+            let output = args.output.join(&path);
+            let output = output.to_string_lossy();
+
+            let graph = args.graph.read(&input)?;
+            let get_api = || registry.backend(api, args.canvas.width, args.canvas.height, &[]);
+            let hints = LayoutHints::default();
+            graph
+                .draw_svg_with(
+                    &get_api,
+                    &registry.layout(np, &[])?,
+                    &hints,
+                    &args.render.options(),
+                    &output,
+                )
+                .map(|_| Picture {
+                    caption: format!("{} / {}", np, api),
+                    path,
+                })
+        })
+        .collect::<Vec<_>>();
+
+    for result in results {
+        match result {
+            Ok(picture) => entry.pictures.push(picture),
+            Err(e) => entry.errors.push(e),
+        }
+    }
+    entry
+}
+
+pub fn run(registry: &Registry, args: GalleryArgs) -> Result<(), String> {
+    // unknown names fail once here rather than for every graph
+    for np in args.node_projectors.iter() {
+        registry.layout(np, &[])?;
+    }
+    let mut extensions = HashMap::new();
+    for api in args.draw_using.iter() {
+        let draw = registry.backend(api, args.canvas.width, args.canvas.height, &[])?;
+        extensions.insert(api.as_str(), draw.extension());
+    }
+    let files = find_graphs(&args.dir)?;
+    if files.is_empty() {
+        return Err(format!("No graphs found in '{}'", args.dir.display()));
+    }
+
+    let names = files
+        .iter()
+        .map(|file| {
+            file.strip_prefix(&args.dir)
+                .unwrap_or(file)
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    for name in names.iter() {
+        let dir = args.output.join(name);
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Unable to create '{}': {}", dir.display(), e))?;
+    }
+
+    let entries = files
+        .par_iter()
+        .zip(names.par_iter())
+        .map(|(file, name)| render_graph(registry, &extensions, &args, file, name))
+        .collect::<Vec<_>>();
+
+    let failed = entries.iter().filter(|e| !e.errors.is_empty()).count();
+    for entry in entries.iter() {
+        for error in entry.errors.iter() {
+            eprintln!("{}: {}", entry.name, error);
+        }
+    }
+
+    let index = args.output.join("index.html");
+    create_parent_dir(&index)?;
+    let title = format!("Graphs from {}", args.dir.display());
+    fs::write(&index, gallery::render_index(&title, &entries))
+        .map_err(|e| format!("Unable to write '{}': {}", index.display(), e))?;
+
+    if !args.no_open {
+        open_file(&index.to_string_lossy())?;
+    }
+    if failed > 0 {
+        return Err(format!(
+            "{} of {} graphs failed to render",
+            failed,
+            entries.len()
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,386 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use my_lib::{
+    graph::{
+        bundling::EdgeBundling,
+        labels::LabelMode,
+        layout::{self, LayoutHints, Positions},
+        render::RenderOptions,
+        subgraph::Subgraph,
+        traits::{DrawGraph, Layout, ReadGraph},
+    },
+    registry::{self, Registry},
+};
+use petgraph::{matrix_graph::MatrixGraph, Graph, Undirected};
+
+mod gallery;
+mod timeline;
+mod watch;
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    draw: DrawArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Render every graph of a directory into an HTML gallery
+    Gallery(gallery::GalleryArgs),
+    /// Draw a sequence of graphs keeping shared nodes in place
+    Timeline(timeline::TimelineArgs),
+    /// List registered drawing apis and node projectors with their options
+    List,
+}
+
+#[derive(clap::Args)]
+struct DrawArgs {
+    #[arg(short, long, value_enum, default_value_t = GraphType::List)]
+    graph: GraphType,
+    /// Name of a registered node projector, see `list`
+    #[arg(short, long, default_value = "circle")]
+    node_projector: String,
+    /// Name of a registered drawing api, see `list`
+    #[arg(short, long, required = true)]
+    draw_using: Option<String>,
+    #[command(flatten)]
+    options: OptionArgs,
+    /// Graph file, or "-" to read the graph from stdin
+    #[arg(short, long, required = true)]
+    file: Option<String>,
+    /// Where to write the picture, ./tmp/file.<extension of the api> by default;
+    /// missing directories are created
+    #[arg(short, long)]
+    output: Option<String>,
+    /// Don't open the picture after drawing
+    #[arg(long)]
+    no_open: bool,
+    #[command(flatten)]
+    canvas: CanvasArgs,
+    /// Start from node positions saved by --save-layout
+    #[arg(long)]
+    load_layout: Option<String>,
+    /// Save node positions to reuse them with --load-layout
+    #[arg(long)]
+    save_layout: Option<String>,
+    /// Labels of nodes that keep their positions from --load-layout, comma separated
+    #[arg(long, value_delimiter = ',', requires = "load_layout")]
+    pin: Vec<String>,
+    /// Redraw whenever the graph or the loaded layout changes,
+    /// a layout that is also saved isn't watched
+    #[arg(short, long)]
+    watch: bool,
+    #[command(flatten)]
+    render: RenderArgs,
+    #[command(flatten)]
+    subgraph: SubgraphArgs,
+}
+
+#[derive(clap::Args, Clone, Copy)]
+struct RenderArgs {
+    /// Bundle similar edges together and draw them as curves
+    #[arg(long)]
+    bundle_edges: bool,
+    #[arg(long, value_enum, default_value_t = Labels::None)]
+    labels: Labels,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+enum Labels {
+    None,
+    /// Next to every node
+    Plain,
+    /// Moved away from other labels and nodes, hidden if there's no room
+    Placed,
+}
+
+impl RenderArgs {
+    fn options(self) -> RenderOptions {
+        RenderOptions {
+            bundling: self.bundle_edges.then(EdgeBundling::new),
+            labels: match self.labels {
+                Labels::None => LabelMode::None,
+                Labels::Plain => LabelMode::Plain,
+                Labels::Placed => LabelMode::Placed,
+            },
+            subgraph: None,
+        }
+    }
+}
+
+/// Part of the graph to draw, at most one
+#[derive(clap::Args)]
+struct SubgraphArgs {
+    /// Draw only nodes at most --hops edges away from this one
+    #[arg(long, group = "subgraph")]
+    ego: Option<String>,
+    #[arg(long, default_value_t = 1, requires = "ego")]
+    hops: usize,
+    /// Draw only these nodes and edges between them, comma separated
+    #[arg(long, value_delimiter = ',', group = "subgraph")]
+    induced: Vec<String>,
+    /// Draw only the largest connected component
+    #[arg(long, group = "subgraph")]
+    largest_component: bool,
+}
+
+impl SubgraphArgs {
+    fn subgraph(&self) -> Option<Subgraph> {
+        if let Some(center) = &self.ego {
+            Some(Subgraph::Ego {
+                center: center.clone(),
+                hops: self.hops,
+            })
+        } else if !self.induced.is_empty() {
+            Some(Subgraph::Induced(self.induced.clone()))
+        } else if self.largest_component {
+            Some(Subgraph::LargestComponent)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(clap::Args, Clone)]
+struct OptionArgs {
+    /// Option of the node projector as name=value, can be repeated
+    #[arg(short = 'N', long = "projector-option", value_parser = registry::parse_option)]
+    projector: Vec<(String, String)>,
+    /// Option of the drawing api as name=value, can be repeated
+    #[arg(short = 'D', long = "api-option", value_parser = registry::parse_option)]
+    api: Vec<(String, String)>,
+}
+
+#[derive(clap::Args, Clone, Copy)]
+struct CanvasArgs {
+    #[arg(long, default_value_t = 1080, value_parser = clap::value_parser!(u32).range(1..))]
+    width: u32,
+    #[arg(long, default_value_t = 1080, value_parser = clap::value_parser!(u32).range(1..))]
+    height: u32,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+enum GraphType {
+    Matrix,
+    List,
+}
+
+impl GraphType {
+    fn read(self, input: &str) -> Result<Box<dyn DrawGraph<Box<dyn Layout>>>, String> {
+        Ok(match self {
+            GraphType::Matrix => Box::new(
+                MatrixGraph::<usize, (), Undirected, Option<()>, usize>::read_from_str(input)?,
+            ),
+            GraphType::List => {
+                Box::new(Graph::<usize, (), Undirected, usize>::read_from_str(input)?)
+            }
+        })
+    }
+}
+
+fn read_input(file: &str) -> Result<String, String> {
+    if file == "-" {
+        let mut input = String::new();
+        io::stdin()
+            .read_to_string(&mut input)
+            .map_err(|e| format!("Unable to read the graph from stdin: {}", e))?;
+        Ok(input)
+    } else {
+        fs::read_to_string(file).map_err(|e| format!("Unable to read '{}': {}", file, e))
+    }
+}
+
+/// Creates missing parent directories of `file`
+fn create_parent_dir(file: &Path) -> Result<(), String> {
+    match file.parent() {
+        Some(dir) => fs::create_dir_all(dir)
+            .map_err(|e| format!("Unable to create '{}': {}", dir.display(), e)),
+        None => Ok(()),
+    }
+}
+
+fn open_file(file: &str) -> Result<(), String> {
+    open::that(file)
+        .map(|_| ())
+        .map_err(|e| format!("Unable to open '{}': {}", file, e))
+}
+
+impl DrawArgs {
+    /// Both are required unless a subcommand is given
+    fn required(&self) -> (&str, &str) {
+        match (&self.draw_using, &self.file) {
+            (Some(draw_using), Some(file)) => (draw_using, file),
+            _ => unreachable!("clap checks required arguments"),
+        }
+    }
+
+    /// Picture file, the default is set by `draw` before drawing
+    fn output(&self) -> &str {
+        self.output
+            .as_deref()
+            .expect("draw sets the default output")
+    }
+}
+
+/// Reads, lays out and draws the graph once.
+/// Nodes missing in --load-layout start from `previous` positions.
+fn draw_once(
+    registry: &Registry,
+    args: &DrawArgs,
+    previous: &Positions,
+) -> Result<Positions, String> {
+    let (draw_using, file) = args.required();
+
+    let node_projector = registry.layout(&args.node_projector, &args.options.projector)?;
+    let draw_api_get = || {
+        registry.backend(
+            draw_using,
+            args.canvas.width,
+            args.canvas.height,
+            &args.options.api,
+        )
+    };
+
+    let input = read_input(file)?;
+    let graph = args
+        .graph
+        .read(&input)
+        .map_err(|e| format!("Malformed graph in '{}': {}", file, e))?;
+
+    let mut hints = LayoutHints {
+        start: previous.clone(),
+        pinned: args.pin.iter().cloned().collect(),
+    };
+    if let Some(file) = &args.load_layout {
+        let positions =
+            fs::read_to_string(file).map_err(|e| format!("Unable to read '{}': {}", file, e))?;
+        let loaded = layout::read_positions(&positions)
+            .map_err(|e| format!("Malformed layout in '{}': {}", file, e))?;
+        let missing = args
+            .pin
+            .iter()
+            .filter(|label| !loaded.contains_key(*label))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(format!(
+                "Pinned nodes without a position in '{}': {}",
+                file,
+                missing.join(", ")
+            ));
+        }
+        hints.start.extend(loaded);
+    }
+
+    let options = RenderOptions {
+        subgraph: args.subgraph.subgraph(),
+        ..args.render.options()
+    };
+    create_parent_dir(Path::new(args.output()))?;
+    let positions = graph.draw_svg_with(
+        &draw_api_get,
+        &node_projector,
+        &hints,
+        &options,
+        args.output(),
+    )?;
+
+    if let Some(file) = &args.save_layout {
+        // nodes left out of the subgraph keep their loaded positions
+        let mut saved = match options.subgraph {
+            Some(_) => hints.start,
+            None => Positions::new(),
+        };
+        saved.extend(positions.clone());
+        create_parent_dir(Path::new(file))?;
+        fs::write(file, layout::write_positions(&saved))
+            .map_err(|e| format!("Unable to write '{}': {}", file, e))?;
+    }
+    Ok(positions)
+}
+
+fn draw(registry: &Registry, mut args: DrawArgs) -> Result<(), String> {
+    if args.output.is_none() {
+        // the file is named after the format the api writes, like file.pdf
+        let (draw_using, _) = args.required();
+        let api = registry.backend(
+            draw_using,
+            args.canvas.width,
+            args.canvas.height,
+            &args.options.api,
+        )?;
+        args.output = Some(format!("./tmp/file.{}", api.extension()));
+    }
+    if args.watch {
+        return watch::run(registry, args);
+    }
+    draw_once(registry, &args, &Positions::new())?;
+    if !args.no_open {
+        open_file(args.output())?;
+    }
+    Ok(())
+}
+
+// cargo run --bin draw -- -d simple-svg -f "resources/graphs/n_40_pr_0.2" -n circle
+// cat input.txt | cargo run --bin draw -- -d draw -f - -o out/graph.svg --no-open
+// cargo run --bin draw -- -d draw -f input.txt -n force --load-layout l.txt --save-layout l.txt --pin 1,2
+// cargo run --bin draw -- -d simple-svg -f input.txt --watch
+// cargo run --bin draw -- -d draw -f resources/graphs/n_40_pr_0.2 -n force --bundle-edges --labels placed
+// cargo run --bin draw -- gallery resources/graphs -o tmp/gallery -n circle,spiral
+// cargo run --bin draw -- timeline step1 step2 step3 -o tmp/timeline.svg
+// cargo run --bin draw -- -d draw -f input.txt -n spiral -N turns=3 -N a=5
+// cargo run --bin draw -- list
+// cargo run --bin draw -- -d draw -f input.txt --ego 5 --hops 2
+fn list(registry: &Registry) {
+    let print_options = |options: &[registry::OptionSpec]| {
+        for option in options {
+            println!(
+                "    {}={}  {}",
+                option.name,
+                option.shown_default(),
+                option.description
+            );
+        }
+    };
+    println!("Drawing apis (-d):");
+    for entry in registry.backends() {
+        println!("  {}  {}", entry.name, entry.description);
+        print_options(&entry.options);
+    }
+    println!("Node projectors (-n):");
+    for entry in registry.layouts() {
+        println!("  {}  {}", entry.name, entry.description);
+        print_options(&entry.options);
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let registry = Registry::with_builtins();
+    let res = match cli.command {
+        Some(Command::Gallery(args)) => gallery::run(&registry, args),
+        Some(Command::Timeline(args)) => timeline::run(&registry, args),
+        Some(Command::List) => {
+            list(&registry);
+            Ok(())
+        }
+        None => draw(&registry, cli.draw),
+    };
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("Error: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
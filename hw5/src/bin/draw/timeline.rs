@@ -0,0 +1,110 @@
+use std::{fs, path::PathBuf};
+
+use clap::ValueEnum;
+use my_lib::{
+    graph::{layout::LayoutHints, render::draw_view, traits::ReadGraph, view::ToGraphView},
+    registry::Registry,
+    timeline,
+};
+use petgraph::{Graph, Undirected};
+
+use crate::{create_parent_dir, open_file, read_input, CanvasArgs, OptionArgs, RenderArgs};
+
+#[derive(clap::Args)]
+pub struct TimelineArgs {
+    /// Graph files, one per time step, in order
+    #[arg(required = true)]
+    files: Vec<String>,
+    /// Directory for frames or file for the animation
+    #[arg(short, long)]
+    output: PathBuf,
+    #[arg(long, value_enum, default_value_t = TimelineFormat::Animated)]
+    format: TimelineFormat,
+    /// Name of a registered node projector, see `list`
+    #[arg(short, long, default_value = "force")]
+    node_projector: String,
+    /// Registered api to draw frames with
+    #[arg(short, long, default_value = "simple-svg")]
+    draw_using: String,
+    #[command(flatten)]
+    options: OptionArgs,
+    /// How long every step is shown in the animation
+    #[arg(long, default_value_t = 1.0)]
+    step_seconds: f32,
+    /// Don't open the result after drawing
+    #[arg(long)]
+    no_open: bool,
+    #[command(flatten)]
+    canvas: CanvasArgs,
+    /// Only for frames
+    #[command(flatten)]
+    render: RenderArgs,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+enum TimelineFormat {
+    /// One picture per step: frame_000.svg, frame_001.svg, ... (or .pdf for pdf)
+    Frames,
+    /// Single SVG animated with SMIL
+    Animated,
+}
+
+pub fn run(registry: &Registry, args: TimelineArgs) -> Result<(), String> {
+    if !args.step_seconds.is_finite() || args.step_seconds <= 0.0 {
+        return Err("--step-seconds must be positive".to_string());
+    }
+    let steps = args
+        .files
+        .iter()
+        .map(|file| {
+            let input = read_input(file)?;
+            Graph::<usize, (), Undirected, usize>::read_from_str(&input)
+                .map(|graph| graph.to_view())
+                .map_err(|e| format!("Malformed graph in '{}': {}", file, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let layout = registry.layout(&args.node_projector, &args.options.projector)?;
+    let points = timeline::layout_steps(&steps, layout.as_ref(), &LayoutHints::default());
+
+    let opened = match args.format {
+        TimelineFormat::Frames => {
+            fs::create_dir_all(&args.output)
+                .map_err(|e| format!("Unable to create '{}': {}", args.output.display(), e))?;
+            let mut first = None;
+            for (i, (view, points)) in steps.iter().zip(points.iter()).enumerate() {
+                let mut draw = registry.backend(
+                    &args.draw_using,
+                    args.canvas.width,
+                    args.canvas.height,
+                    &args.options.api,
+                )?;
+                let file = args
+                    .output
+                    .join(format!("frame_{:03}.{}", i, draw.extension()));
+                draw_view(view, points, &args.render.options(), draw.as_mut());
+                draw.export(&file.to_string_lossy())?;
+                first.get_or_insert(file);
+            }
+            first.expect("at least one file is required")
+        }
+        TimelineFormat::Animated => {
+            let svg = timeline::render_animated_svg(
+                &steps,
+                &points,
+                args.canvas.width,
+                args.canvas.height,
+                args.step_seconds,
+            );
+            create_parent_dir(&args.output)?;
+            fs::write(&args.output, svg)
+                .map_err(|e| format!("Unable to write '{}': {}", args.output.display(), e))?;
+            args.output.clone()
+        }
+    };
+
+    if !args.no_open {
+        open_file(&opened.to_string_lossy())?;
+    }
+    Ok(())
+}
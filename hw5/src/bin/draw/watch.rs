@@ -0,0 +1,56 @@
+use std::{fs, thread, time::Duration, time::SystemTime};
+
+use my_lib::{graph::layout::Positions, registry::Registry};
+
+use crate::{draw_once, open_file, DrawArgs};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Last modification times of the watched files, `None` for missing files
+fn modified(files: &[String]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|file| fs::metadata(file).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// Redraws the graph every time one of its input files changes.
+/// Errors are reported and the watching goes on, runs until interrupted.
+pub fn run(registry: &Registry, args: DrawArgs) -> Result<(), String> {
+    let (_, file) = args.required();
+    if file == "-" {
+        return Err("Unable to watch stdin, pass a file to --file".to_string());
+    }
+    // the layout we save ourselves would trigger a redraw after every drawing
+    let mut files = vec![file.to_string()];
+    files.extend(
+        args.load_layout
+            .iter()
+            .filter(|layout| args.save_layout.as_ref() != Some(*layout))
+            .cloned(),
+    );
+
+    let mut previous = Positions::new();
+    let mut opened = args.no_open;
+    loop {
+        // taken before drawing, so a change made while drawing triggers the next one
+        let last = modified(&files);
+        match draw_once(registry, &args, &previous) {
+            Ok(positions) => {
+                previous = positions;
+                println!("Drawn {}", args.output());
+                if !opened {
+                    opened = true;
+                    if let Err(message) = open_file(args.output()) {
+                        eprintln!("Error: {}", message);
+                    }
+                }
+            }
+            Err(message) => eprintln!("Error: {}", message),
+        }
+
+        while modified(&files) == last {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
@@ -1,15 +1,20 @@
-use draw::{render, Canvas, Color, Drawing, LineBuilder, Shape, Style, SvgRenderer};
+use std::{fs, path::Path};
 
-use crate::drawing_api::DrawingApi;
+use draw::{render::Renderer, Canvas, Color, Drawing, LineBuilder, Shape, Style, SvgRenderer};
+
+use crate::drawing_api::{self, DrawingApi, Point};
 
 pub struct Draw {
     canvas: Canvas,
+    /// draw has no text, it's added to the rendered svg
+    texts: Vec<(Point, String, u32)>,
 }
 
 impl Draw {
-    pub fn new() -> Self {
+    pub fn new(width: u32, height: u32) -> Self {
         Draw {
-            canvas: Canvas::new(1080, 1080),
+            canvas: Canvas::new(width, height),
+            texts: vec![],
         }
     }
 }
@@ -23,7 +28,7 @@ impl DrawingApi for Draw {
         self.canvas.height
     }
 
-    fn draw_line(&mut self, from: &crate::drawing_api::Point, to: &crate::drawing_api::Point) {
+    fn draw_line(&mut self, from: &Point, to: &Point) {
         let line = Drawing::new()
             .with_shape(
                 LineBuilder::new(from.x as f32, from.y as f32)
@@ -35,7 +40,7 @@ impl DrawingApi for Draw {
         self.canvas.display_list.add(line);
     }
 
-    fn draw_circle(&mut self, position: &crate::drawing_api::Point, radius: u32) {
+    fn draw_circle(&mut self, position: &Point, radius: u32) {
         let circle = Drawing::new()
             .with_shape(Shape::Circle { radius })
             .with_xy(position.x as f32, position.y as f32)
@@ -44,8 +49,54 @@ impl DrawingApi for Draw {
         self.canvas.display_list.add(circle);
     }
 
-    fn export_svg(&self, file: &String) {
-        // save the canvas as an svg
-        render::save(&self.canvas, file, SvgRenderer::new()).expect("Failed to save");
+    fn draw_curve(&mut self, points: &[Point]) {
+        let Some(start) = points.first() else {
+            return;
+        };
+        let mut line = LineBuilder::new(start.x as f32, start.y as f32);
+        for (control, to) in drawing_api::quadratic_segments(points) {
+            line = line.curve_to(to.x as f32, to.y as f32, control.x as f32, control.y as f32);
+        }
+        let curve = Drawing::new()
+            .with_shape(line.build())
+            .with_style(Style::stroked(5, Color::gray(50)));
+
+        self.canvas.display_list.add(curve);
     }
+
+    fn draw_text(&mut self, position: &Point, text: &str, size: u32) {
+        self.texts.push((*position, text.to_string(), size));
+    }
+
+    fn export(&self, file: &str) -> Result<(), String> {
+        if Path::new(file).is_dir() {
+            return Err(format!("Unable to write '{}': is a directory", file));
+        }
+        let mut svg = String::from_utf8(SvgRenderer::new().render(&self.canvas))
+            .map_err(|e| format!("Unable to render '{}': {}", file, e))?;
+
+        let closing = svg.rfind("</svg>").unwrap_or(svg.len());
+        let texts = self
+            .texts
+            .iter()
+            .map(|(p, text, size)| {
+                format!(
+                    r#"<text x="{}" y="{}" font-size="{}">{}</text>"#,
+                    p.x,
+                    p.y,
+                    size,
+                    escape(text)
+                )
+            })
+            .collect::<String>();
+        svg.insert_str(closing, &texts);
+
+        fs::write(file, svg).map_err(|e| format!("Unable to write '{}': {}", file, e))
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
 }
@@ -1,2 +1,3 @@
 pub mod draw;
+pub mod pdf;
 pub mod simple_svg;
@@ -0,0 +1,347 @@
+use std::{fmt::Write, fs, path::Path};
+
+use crate::drawing_api::{self, DrawingApi, Point};
+
+/// Size of the page in PDF points, 1/72 inch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PageSize {
+    /// One point per pixel of the drawing area
+    Canvas,
+    Fixed {
+        width: f32,
+        height: f32,
+    },
+}
+
+impl PageSize {
+    /// "canvas" or a paper name: a3, a4, a5, letter, legal
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        let (width, height) = match name.to_lowercase().as_str() {
+            "canvas" => return Ok(PageSize::Canvas),
+            "a3" => (842.0, 1191.0),
+            "a4" => (595.0, 842.0),
+            "a5" => (420.0, 595.0),
+            "letter" => (612.0, 792.0),
+            "legal" => (612.0, 1008.0),
+            _ => {
+                return Err(format!(
+                    "unknown page size '{}', expected canvas, a3, a4, a5, letter or legal",
+                    name
+                ))
+            }
+        };
+        Ok(PageSize::Fixed { width, height })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    /// Parses "#rrggbb" or "rrggbb"
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(|| format!("bad colour '{}', expected #rrggbb", s))
+        };
+        if hex.len() != 6 {
+            return Err(format!("bad colour '{}', expected #rrggbb", s));
+        }
+        Ok(Rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    /// Operands of the PDF colour operators
+    fn operands(self) -> String {
+        format!(
+            "{} {} {}",
+            num(self.0 as f32 / 255.0),
+            num(self.1 as f32 / 255.0),
+            num(self.2 as f32 / 255.0)
+        )
+    }
+}
+
+/// Writes single page vector PDF with the standard Helvetica font, so nothing is embedded
+pub struct Pdf {
+    width: u32,
+    height: u32,
+    pub page: PageSize,
+    pub landscape: bool,
+    /// Margin around the drawing in points, only for fixed page sizes
+    pub margin: f32,
+    pub line_color: Rgb,
+    pub node_color: Rgb,
+    pub text_color: Rgb,
+    /// Content stream in canvas coordinates
+    content: String,
+}
+
+impl Pdf {
+    pub fn new(width: u32, height: u32) -> Self {
+        Pdf {
+            width,
+            height,
+            page: PageSize::Canvas,
+            landscape: false,
+            margin: 36.0,
+            line_color: Rgb(50, 50, 50),
+            node_color: Rgb(0, 0, 0),
+            text_color: Rgb(0, 0, 0),
+            content: String::new(),
+        }
+    }
+
+    /// Page size and the transformation of canvas coordinates into it:
+    /// the drawing is scaled to fit inside the margins and centered
+    fn page_and_transform(&self) -> ((f32, f32), (f32, f32, f32)) {
+        let (width, height) = (self.width as f32, self.height as f32);
+        let (page_width, page_height) = match self.page {
+            PageSize::Canvas => return ((width, height), (1.0, 0.0, height)),
+            PageSize::Fixed { width, height } if self.landscape => (height, width),
+            PageSize::Fixed { width, height } => (width, height),
+        };
+        let margin = self.margin.clamp(0.0, page_width.min(page_height) / 2.0);
+        let scale =
+            ((page_width - 2.0 * margin) / width).min((page_height - 2.0 * margin) / height);
+        let left = (page_width - width * scale) / 2.0;
+        let top = (page_height - height * scale) / 2.0;
+        ((page_width, page_height), (scale, left, page_height - top))
+    }
+
+    /// Whole file, byte offsets of objects in the cross-reference table must be exact
+    fn render(&self) -> Vec<u8> {
+        let ((page_width, page_height), (scale, left, top)) = self.page_and_transform();
+        // y axis of PDF goes up, the canvas one goes down
+        let content = format!(
+            "q {} 0 0 {} {} {} cm\n{}Q\n",
+            num(scale),
+            num(-scale),
+            num(left),
+            num(top),
+            self.content
+        );
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Contents 4 0 R \
+                 /Resources << /Font << /F1 5 0 R >> >> >>",
+                num(page_width),
+                num(page_height)
+            ),
+            format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                content.len(),
+                content
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+        ];
+
+        let mut out = Vec::from(&b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n"[..]);
+        let mut offsets = vec![];
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).bytes());
+        }
+        let xref = out.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(table, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            table,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        );
+        out.extend(table.bytes());
+        out
+    }
+}
+
+/// Number without needless digits, PDF has no exponent notation
+fn num(x: f32) -> String {
+    let s = format!("{:.3}", x);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+/// PDF string literal in WinAnsi encoding, characters it lacks become '?'
+fn pdf_string(text: &str) -> String {
+    let mut out = String::from("(");
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            // Latin-1 part of WinAnsi
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(out, "\\{:03o}", c as u32);
+            }
+            _ => out.push('?'),
+        }
+    }
+    out.push(')');
+    out
+}
+
+impl DrawingApi for Pdf {
+    fn get_area_width(&self) -> u32 {
+        self.width
+    }
+
+    fn get_area_height(&self) -> u32 {
+        self.height
+    }
+
+    fn draw_line(&mut self, from: &Point, to: &Point) {
+        let _ = writeln!(
+            self.content,
+            "{} RG 5 w 1 J {} {} m {} {} l S",
+            self.line_color.operands(),
+            from.x,
+            from.y,
+            to.x,
+            to.y
+        );
+    }
+
+    fn draw_circle(&mut self, position: &Point, radius: u32) {
+        // four cubic Bezier arcs, the usual approximation of a circle
+        const K: f32 = 0.552_284_8;
+        let (x, y, r) = (position.x as f32, position.y as f32, radius as f32);
+        let k = K * r;
+        let _ = writeln!(
+            self.content,
+            "{} rg {} {} m {} {} {} {} {} {} c {} {} {} {} {} {} c {} {} {} {} {} {} c {} {} {} {} {} {} c f",
+            self.node_color.operands(),
+            num(x + r), num(y),
+            num(x + r), num(y + k), num(x + k), num(y + r), num(x), num(y + r),
+            num(x - k), num(y + r), num(x - r), num(y + k), num(x - r), num(y),
+            num(x - r), num(y - k), num(x - k), num(y - r), num(x), num(y - r),
+            num(x + k), num(y - r), num(x + r), num(y - k), num(x + r), num(y),
+        );
+    }
+
+    fn draw_curve(&mut self, points: &[Point]) {
+        let Some(start) = points.first() else {
+            return;
+        };
+        let mut path = format!(
+            "{} RG 5 w 1 J {} {} m",
+            self.line_color.operands(),
+            start.x,
+            start.y
+        );
+        let mut from = (start.x as f32, start.y as f32);
+        for (control, to) in drawing_api::quadratic_segments(points) {
+            // quadratic Bezier as a cubic one
+            let (cx, cy) = (control.x as f32, control.y as f32);
+            let (tx, ty) = (to.x as f32, to.y as f32);
+            let _ = write!(
+                path,
+                " {} {} {} {} {} {} c",
+                num(from.0 + 2.0 / 3.0 * (cx - from.0)),
+                num(from.1 + 2.0 / 3.0 * (cy - from.1)),
+                num(tx + 2.0 / 3.0 * (cx - tx)),
+                num(ty + 2.0 / 3.0 * (cy - ty)),
+                num(tx),
+                num(ty)
+            );
+            from = (tx, ty);
+        }
+        self.content.push_str(&path);
+        self.content.push_str(" S\n");
+    }
+
+    fn draw_text(&mut self, position: &Point, text: &str, size: u32) {
+        // the text matrix flips y back, otherwise glyphs are upside down
+        let _ = writeln!(
+            self.content,
+            "{} rg BT /F1 {} Tf 1 0 0 -1 {} {} Tm {} Tj ET",
+            self.text_color.operands(),
+            size,
+            position.x,
+            position.y,
+            pdf_string(text)
+        );
+    }
+
+    fn export(&self, file: &str) -> Result<(), String> {
+        if Path::new(file).is_dir() {
+            return Err(format!("Unable to write '{}': is a directory", file));
+        }
+        fs::write(file, self.render()).map_err(|e| format!("Unable to write '{}': {}", file, e))
+    }
+
+    fn extension(&self) -> &'static str {
+        "pdf"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xref_points_at_objects() {
+        let mut pdf = Pdf::new(100, 50);
+        pdf.draw_line(&Point { x: 0, y: 0 }, &Point { x: 100, y: 50 });
+        pdf.draw_circle(&Point { x: 50, y: 25 }, 10);
+        pdf.draw_text(&Point { x: 10, y: 40 }, "a (b) é", 12);
+        let bytes = pdf.render();
+        let text = String::from_utf8_lossy(&bytes);
+
+        let start = text.rfind("startxref\n").unwrap() + "startxref\n".len();
+        let xref = text[start..]
+            .lines()
+            .next()
+            .unwrap()
+            .parse::<usize>()
+            .unwrap();
+        assert!(bytes[xref..].starts_with(b"xref"));
+        // the binary comment in the header isn't UTF-8, so offsets are taken from bytes
+        let table = String::from_utf8_lossy(&bytes[xref..]).to_string();
+        let entries = table.lines().skip(3).take(5);
+        for (i, entry) in entries.enumerate() {
+            let offset = entry[..10].parse::<usize>().unwrap();
+            assert!(bytes[offset..].starts_with(format!("{} 0 obj", i + 1).as_bytes()));
+        }
+        assert!(text.contains("(a \\(b\\) \\351) Tj"));
+        assert!(text.contains("/MediaBox [0 0 100 50]"));
+    }
+
+    #[test]
+    fn fits_page() {
+        let mut pdf = Pdf::new(200, 100);
+        pdf.page = PageSize::from_name("A4").unwrap();
+        pdf.margin = 0.0;
+        // the wide drawing fills the width of the page and is centered vertically
+        let ((w, h), (scale, left, top)) = pdf.page_and_transform();
+        assert_eq!((595.0, 842.0), (w, h));
+        assert_eq!((2.975, 0.0), (scale, left));
+        assert!((top - (842.0 + 297.5) / 2.0).abs() < 1e-3);
+
+        pdf.landscape = true;
+        assert_eq!((842.0, 595.0), pdf.page_and_transform().0);
+        assert!(PageSize::from_name("b5").is_err());
+    }
+
+    #[test]
+    fn colours() {
+        assert_eq!(Rgb(255, 0, 16), Rgb::parse("#ff0010").unwrap());
+        assert_eq!(Rgb(0, 0, 0), Rgb::parse("000000").unwrap());
+        assert!(Rgb::parse("#fff").is_err());
+        assert!(Rgb::parse("#gg0000").is_err());
+        assert_eq!("1 0 0.063", Rgb(255, 0, 16).operands());
+    }
+}
@@ -1,8 +1,8 @@
 use std::fs;
 
-use simplesvg::{Attr, ColorAttr, Fig, Svg};
+use simplesvg::{Attr, ColorAttr, Fig, Svg, Trans};
 
-use crate::drawing_api::DrawingApi;
+use crate::drawing_api::{self, DrawingApi};
 
 pub struct SimpleSvg {
     width: u32,
@@ -11,15 +11,23 @@ pub struct SimpleSvg {
 }
 
 impl SimpleSvg {
-    pub fn new() -> Self {
+    pub fn new(width: u32, height: u32) -> Self {
         SimpleSvg {
-            width: 1080,
-            height: 1080,
+            width,
+            height,
             figs: vec![],
         }
     }
 }
 
+const CURVE_STEPS: usize = 8;
+
+fn line_style(fig: Fig) -> Fig {
+    let color = ColorAttr::Color(0, 255, 255);
+    let style = Attr::default().stroke_width(5.0).stroke(color);
+    Fig::Styled(style, Box::new(fig))
+}
+
 impl DrawingApi for SimpleSvg {
     fn get_area_width(&self) -> u32 {
         self.width
@@ -31,12 +39,7 @@ impl DrawingApi for SimpleSvg {
 
     fn draw_line(&mut self, from: &crate::drawing_api::Point, to: &crate::drawing_api::Point) {
         let fig = Fig::Line(from.x as f32, from.y as f32, to.x as f32, to.y as f32);
-
-        let color = ColorAttr::Color(0, 255, 255);
-        let style = Attr::default().stroke_width(5.0).stroke(color);
-
-        let fig = Fig::Styled(style, Box::new(fig));
-        self.figs.push(fig);
+        self.figs.push(line_style(fig));
     }
 
     fn draw_circle(&mut self, position: &crate::drawing_api::Point, radius: u32) {
@@ -44,8 +47,40 @@ impl DrawingApi for SimpleSvg {
         self.figs.push(fig);
     }
 
-    fn export_svg(&self, file: &String) {
+    /// simplesvg has no paths, so the curve is approximated with lines
+    fn draw_curve(&mut self, points: &[crate::drawing_api::Point]) {
+        let Some(start) = points.first() else {
+            return;
+        };
+        let mut lines = vec![];
+        let mut from = (start.x as f32, start.y as f32);
+        for (control, to) in drawing_api::quadratic_segments(points) {
+            let (cx, cy) = (control.x as f32, control.y as f32);
+            let (tx, ty) = (to.x as f32, to.y as f32);
+            let start = from;
+            for step in 1..=CURVE_STEPS {
+                let t = step as f32 / CURVE_STEPS as f32;
+                let u = 1.0 - t;
+                let x = u * u * start.0 + 2.0 * u * t * cx + t * t * tx;
+                let y = u * u * start.1 + 2.0 * u * t * cy + t * t * ty;
+                lines.push(Fig::Line(from.0, from.1, x, y));
+                from = (x, y);
+            }
+        }
+        self.figs.push(line_style(Fig::Multiple(lines)));
+    }
+
+    /// simplesvg can't set font size, so the text is scaled from the default 16px
+    fn draw_text(&mut self, position: &crate::drawing_api::Point, text: &str, size: u32) {
+        let trans = Trans::default()
+            .scale(size as f32 / 16.0)
+            .translate(position.x as f32, position.y as f32);
+        let fig = Fig::Text(0.0, 0.0, text.to_string()).transformed(trans);
+        self.figs.push(fig);
+    }
+
+    fn export(&self, file: &str) -> Result<(), String> {
         let output = Svg(self.figs.clone(), self.width, self.height).to_string();
-        fs::write(file, output).expect("Unable to write file");
+        fs::write(file, output).map_err(|e| format!("Unable to write '{}': {}", file, e))
     }
 }
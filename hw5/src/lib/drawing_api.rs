@@ -1,4 +1,4 @@
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Point {
     pub x: u32,
     pub y: u32,
@@ -9,8 +9,21 @@ pub trait DrawingApi {
     fn get_area_height(&self) -> u32;
     fn draw_line(&mut self, from: &Point, to: &Point);
     fn draw_circle(&mut self, position: &Point, radius: u32);
-    /// panics on failure
-    fn export_svg(&self, file: &String);
+    /// Smooth curve from the first point to the last one,
+    /// points in between pull it like control points of a B-spline
+    fn draw_curve(&mut self, points: &[Point]) {
+        for segment in points.windows(2) {
+            self.draw_line(&segment[0], &segment[1]);
+        }
+    }
+    /// `position` is the left end of the text baseline, `size` is the font size
+    fn draw_text(&mut self, position: &Point, text: &str, size: u32);
+    /// Writes the picture to `file` in the format of the api
+    fn export(&self, file: &str) -> Result<(), String>;
+    /// Extension of files written by `export`
+    fn extension(&self) -> &'static str {
+        "svg"
+    }
 }
 
 /// scales point inside -1:1 square to given area
@@ -24,3 +37,39 @@ pub fn scale(x: f32, y: f32, x_limits: &(u32, u32), y_limits: &(u32, u32)) -> Po
         y: transfer(y, y_limits.0, y_limits.1),
     }
 }
+
+/// Splits curve of `DrawingApi::draw_curve` into quadratic Bezier segments.
+/// Returns (control point, end point) of every segment, the first one starts at `points[0]`.
+pub fn quadratic_segments(points: &[Point]) -> Vec<(Point, Point)> {
+    let n = points.len();
+    if n < 3 {
+        return points.iter().skip(1).map(|p| (*p, *p)).collect();
+    }
+    let middle = |a: &Point, b: &Point| Point {
+        x: (a.x + b.x) / 2,
+        y: (a.y + b.y) / 2,
+    };
+    let mut segments = (1..n - 2)
+        .map(|i| (points[i], middle(&points[i], &points[i + 1])))
+        .collect::<Vec<_>>();
+    segments.push((points[n - 2], points[n - 1]));
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{quadratic_segments, Point};
+
+    #[test]
+    fn segments_end_at_last_point() {
+        let p = |x, y| Point { x, y };
+        assert_eq!(
+            vec![(p(2, 2), p(2, 2))],
+            quadratic_segments(&[p(0, 0), p(2, 2)])
+        );
+        assert_eq!(
+            vec![(p(2, 2), p(3, 2)), (p(4, 2), p(6, 0))],
+            quadratic_segments(&[p(0, 0), p(2, 2), p(4, 2), p(6, 0)])
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt::Write;
+
+use crate::graph::stats::GraphStats;
+
+/// One rendered picture of a graph, `path` is relative to the index file
+pub struct Picture {
+    pub caption: String,
+    pub path: String,
+}
+
+/// Row of the gallery: a graph with all of its pictures
+pub struct GalleryEntry {
+    pub name: String,
+    /// `None` if the graph couldn't be read
+    pub stats: Option<GraphStats>,
+    pub pictures: Vec<Picture>,
+    pub errors: Vec<String>,
+}
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; }
+section { border-bottom: 1px solid #ccc; padding: 1em 0; }
+figure { display: inline-block; margin: 0 1em 1em 0; text-align: center; }
+img, object { width: 240px; height: 240px; border: 1px solid #ddd; background: #fff; }
+table { border-collapse: collapse; }
+td { padding: 0 1em 0 0; }
+.error { color: #b00; }
+";
+
+/// Renders index page of the gallery, pictures link to their full size files
+pub fn render_index(title: &str, entries: &[GalleryEntry]) -> String {
+    let mut out = String::new();
+    // writing into a String never fails
+    let _ = write_index(&mut out, title, entries);
+    out
+}
+
+fn write_index(out: &mut String, title: &str, entries: &[GalleryEntry]) -> std::fmt::Result {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>{}</title>", escape(title))?;
+    writeln!(out, "<style>{}</style>\n</head>\n<body>", STYLE)?;
+    writeln!(out, "<h1>{}</h1>", escape(title))?;
+    for entry in entries {
+        writeln!(out, "<section>\n<h2>{}</h2>", escape(&entry.name))?;
+        if let Some(stats) = &entry.stats {
+            writeln!(out, "<table><tr>")?;
+            writeln!(out, "<td>nodes: {}</td>", stats.nodes)?;
+            writeln!(out, "<td>edges: {}</td>", stats.edges)?;
+            writeln!(out, "<td>components: {}</td>", stats.components)?;
+            writeln!(out, "<td>max degree: {}</td>", stats.max_degree)?;
+            writeln!(out, "<td>density: {:.3}</td>", stats.density)?;
+            writeln!(out, "</tr></table>")?;
+        }
+        for error in entry.errors.iter() {
+            writeln!(out, "<p class=\"error\">{}</p>", escape(error))?;
+        }
+        for picture in entry.pictures.iter() {
+            let path = escape(&picture.path);
+            // browsers don't show PDFs in <img>
+            let preview = if picture.path.ends_with(".pdf") {
+                format!(
+                    "<object data=\"{}\" type=\"application/pdf\"></object>",
+                    path
+                )
+            } else {
+                format!(
+                    "<img src=\"{}\" alt=\"{}\">",
+                    path,
+                    escape(&picture.caption)
+                )
+            };
+            writeln!(
+                out,
+                "<figure><a href=\"{}\">{}</a><figcaption>{}</figcaption></figure>",
+                path,
+                preview,
+                escape(&picture.caption)
+            )?;
+        }
+        writeln!(out, "</section>")?;
+    }
+    writeln!(out, "</body>\n</html>")
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_index, GalleryEntry, Picture};
+
+    #[test]
+    fn escapes_names() {
+        let html = render_index(
+            "graphs",
+            &[GalleryEntry {
+                name: "<a&b>".to_string(),
+                stats: None,
+                pictures: vec![Picture {
+                    caption: "circle / draw".to_string(),
+                    path: "a/circle-draw.svg".to_string(),
+                }],
+                errors: vec!["bad \"line\"".to_string()],
+            }],
+        );
+        assert!(html.contains("<h2>&lt;a&amp;b&gt;</h2>"));
+        assert!(html.contains("<img src=\"a/circle-draw.svg\""));
+        assert!(html.contains("bad &quot;line&quot;"));
+    }
+}
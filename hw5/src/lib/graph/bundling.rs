@@ -0,0 +1,253 @@
+use rayon::prelude::*;
+
+/// Force-directed edge bundling by Holten and van Wijk.
+/// Edges are split into points that are pulled towards the points of similar edges,
+/// so edges going the same way merge into bundles.
+#[derive(Debug, Clone)]
+pub struct EdgeBundling {
+    /// Subdivision cycles, every cycle doubles the number of points of an edge
+    pub cycles: usize,
+    /// Iterations of the first cycle, every next one has 2/3 of the previous
+    pub iterations: usize,
+    /// Max move of a point per iteration at the first cycle, halves every cycle
+    pub step: f32,
+    /// Stiffness of edges
+    pub spring: f32,
+    /// Edges less compatible than this don't attract each other
+    pub compatibility: f32,
+}
+
+impl EdgeBundling {
+    pub fn new() -> Self {
+        EdgeBundling {
+            cycles: 5,
+            iterations: 50,
+            step: 0.04,
+            spring: 0.1,
+            compatibility: 0.6,
+        }
+    }
+}
+
+impl Default for EdgeBundling {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+type Vector = (f32, f32);
+
+fn sub(a: Vector, b: Vector) -> Vector {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn len(a: Vector) -> f32 {
+    (a.0 * a.0 + a.1 * a.1).sqrt()
+}
+
+fn dot(a: Vector, b: Vector) -> f32 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+/// How similar two edges are, from 0 to 1, by angle, length, distance and visibility
+fn compatibility(p: (Vector, Vector), q: (Vector, Vector)) -> f32 {
+    let (vp, vq) = (sub(p.1, p.0), sub(q.1, q.0));
+    let (lp, lq) = (len(vp), len(vq));
+    let angle = dot(vp, vq).abs() / (lp * lq);
+
+    let avg = (lp + lq) / 2.0;
+    let scale = 2.0 / (avg / lp.min(lq) + lp.max(lq) / avg);
+
+    let mid_p = ((p.0 .0 + p.1 .0) / 2.0, (p.0 .1 + p.1 .1) / 2.0);
+    let mid_q = ((q.0 .0 + q.1 .0) / 2.0, (q.0 .1 + q.1 .1) / 2.0);
+    let position = avg / (avg + len(sub(mid_p, mid_q)));
+
+    let visibility = visibility(p, q).min(visibility(q, p));
+    angle * scale * position * visibility
+}
+
+/// How much of `p` is seen from `q` when `q` is projected on the line of `p`
+fn visibility(p: (Vector, Vector), q: (Vector, Vector)) -> f32 {
+    let project = |point: Vector| {
+        let v = sub(p.1, p.0);
+        let t = dot(sub(point, p.0), v) / dot(v, v);
+        (p.0 .0 + t * v.0, p.0 .1 + t * v.1)
+    };
+    let (i0, i1) = (project(q.0), project(q.1));
+    let mid_i = ((i0.0 + i1.0) / 2.0, (i0.1 + i1.1) / 2.0);
+    let mid_p = ((p.0 .0 + p.1 .0) / 2.0, (p.0 .1 + p.1 .1) / 2.0);
+    let span = len(sub(i0, i1));
+    if span == 0.0 {
+        return 0.0;
+    }
+    (1.0 - 2.0 * len(sub(mid_p, mid_i)) / span).max(0.0)
+}
+
+/// Inserts points so that the polyline is split into `parts` segments of equal length
+fn subdivide(line: &[Vector], parts: usize) -> Vec<Vector> {
+    let total = line.windows(2).map(|w| len(sub(w[1], w[0]))).sum::<f32>();
+    let piece = total / parts as f32;
+    let mut result = vec![line[0]];
+    let mut segment = 0;
+    let mut passed = 0.0;
+    for i in 1..parts {
+        let target = piece * i as f32;
+        loop {
+            let segment_len = len(sub(line[segment + 1], line[segment]));
+            if passed + segment_len >= target || segment + 2 == line.len() {
+                let t = if segment_len > 0.0 {
+                    (target - passed) / segment_len
+                } else {
+                    0.0
+                };
+                let (a, b) = (line[segment], line[segment + 1]);
+                result.push((a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t));
+                break;
+            }
+            passed += segment_len;
+            segment += 1;
+        }
+    }
+    result.push(line[line.len() - 1]);
+    result
+}
+
+impl EdgeBundling {
+    /// Returns polyline for each of `edges` between `points` in -1:1 square,
+    /// the first and the last points of a polyline are the ends of the edge
+    pub fn bundle(&self, points: &[Vector], edges: &[(usize, usize)]) -> Vec<Vec<Vector>> {
+        let ends = edges
+            .iter()
+            .map(|(a, b)| (points[*a], points[*b]))
+            .collect::<Vec<_>>();
+        // edges of zero length are self loops or overlapping nodes, they don't bundle
+        let bundled = ends.iter().map(|(a, b)| a != b).collect::<Vec<_>>();
+
+        // compatible edges and whether they go in the opposite direction
+        let neighbors = (0..ends.len())
+            .into_par_iter()
+            .map(|i| {
+                (0..ends.len())
+                    .filter(|j| *j != i && bundled[i] && bundled[*j])
+                    .filter(|j| compatibility(ends[i], ends[*j]) >= self.compatibility)
+                    .map(|j| {
+                        let reversed =
+                            dot(sub(ends[i].1, ends[i].0), sub(ends[j].1, ends[j].0)) < 0.0;
+                        (j, reversed)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut lines = ends.iter().map(|(a, b)| vec![*a, *b]).collect::<Vec<_>>();
+        let mut parts = 1;
+        let mut step = self.step;
+        let mut iterations = self.iterations as f32;
+        for _ in 0..self.cycles {
+            parts *= 2;
+            lines = lines
+                .iter()
+                .zip(bundled.iter())
+                .map(|(line, b)| {
+                    if *b {
+                        subdivide(line, parts)
+                    } else {
+                        line.clone()
+                    }
+                })
+                .collect();
+
+            for _ in 0..iterations.round() as usize {
+                lines = (0..lines.len())
+                    .into_par_iter()
+                    .map(|i| self.move_points(i, &lines, &ends, &neighbors[i], step))
+                    .collect();
+            }
+            step /= 2.0;
+            iterations *= 2.0 / 3.0;
+        }
+        lines
+    }
+
+    fn move_points(
+        &self,
+        i: usize,
+        lines: &[Vec<Vector>],
+        ends: &[(Vector, Vector)],
+        neighbors: &[(usize, bool)],
+        step: f32,
+    ) -> Vec<Vector> {
+        let line = &lines[i];
+        if neighbors.is_empty() {
+            return line.clone();
+        }
+        let n = line.len();
+        let k = self.spring / (len(sub(ends[i].1, ends[i].0)) * (n - 1) as f32);
+        let mut result = line.clone();
+        for (j, point) in result.iter_mut().enumerate().take(n - 1).skip(1) {
+            let p = line[j];
+            let spring = (
+                k * (line[j - 1].0 - p.0 + line[j + 1].0 - p.0),
+                k * (line[j - 1].1 - p.1 + line[j + 1].1 - p.1),
+            );
+            let mut electrostatic = (0.0, 0.0);
+            for (other, reversed) in neighbors {
+                let q = if *reversed {
+                    lines[*other][n - 1 - j]
+                } else {
+                    lines[*other][j]
+                };
+                let d = sub(q, p);
+                let dist = len(d);
+                if dist > 1e-4 {
+                    electrostatic.0 += d.0 / dist;
+                    electrostatic.1 += d.1 / dist;
+                }
+            }
+            let force = (spring.0 + electrostatic.0, spring.1 + electrostatic.1);
+            let force_len = len(force);
+            if force_len > 0.0 {
+                let shift = force_len.min(step) / force_len;
+                *point = (p.0 + force.0 * shift, p.1 + force.1 * shift);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parallel_edges_come_closer() {
+        let points = [(-0.5, -0.1), (0.5, -0.1), (-0.5, 0.1), (0.5, 0.1)];
+        let lines = EdgeBundling::new().bundle(&points, &[(0, 1), (3, 2)]);
+        assert_eq!(lines[0].len(), lines[1].len());
+        // ends stay at the nodes
+        assert_eq!((-0.5, -0.1), lines[0][0]);
+        assert_eq!((0.5, 0.1), lines[1][0]);
+        let middle = lines[0].len() / 2;
+        let gap = len(sub(lines[0][middle], lines[1][middle]));
+        assert!(gap < 0.1, "gap {} didn't shrink", gap);
+    }
+
+    #[test]
+    fn orthogonal_edges_stay_straight() {
+        let points = [(-0.5, 0.0), (0.5, 0.0), (0.0, -0.5), (0.0, 0.5)];
+        let lines = EdgeBundling::new().bundle(&points, &[(0, 1), (2, 3)]);
+        for line in lines {
+            for p in line {
+                assert!(p.0.abs() < 1e-4 || p.1.abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn subdivide_evenly() {
+        let line = subdivide(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)], 4);
+        assert_eq!(5, line.len());
+        assert!((line[2].0 - 1.0).abs() < 1e-6 && line[2].1.abs() < 1e-6);
+        assert_eq!((1.0, 1.0), line[4]);
+    }
+}
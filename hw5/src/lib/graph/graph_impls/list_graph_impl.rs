@@ -1,61 +1,49 @@
-use std::collections::HashMap;
+use std::fmt::Display;
 
 use petgraph::{
-    prelude::NodeIndex, stable_graph::IndexType, visit::IntoNodeIdentifiers, EdgeType, Graph,
-    Undirected,
+    prelude::NodeIndex,
+    stable_graph::IndexType,
+    visit::{EdgeRef, IntoNodeIdentifiers},
+    EdgeType, Graph, Undirected,
 };
 
 use crate::{
-    drawing_api::{self, DrawingApi},
-    graph::traits::{DrawGraph, NodeProjector, ReadGraph},
+    graph::{
+        traits::ReadGraph,
+        view::{GraphView, ToGraphView},
+    },
     utils,
 };
 
-use super::{ConstructGraph, read_graph};
+use super::{read_graph, ConstructGraph};
 
-impl<N, E, Ty, Ix, NP> DrawGraph<NP> for Graph<N, E, Ty, Ix>
+impl<N, E, Ty, Ix> ToGraphView for Graph<N, E, Ty, Ix>
 where
+    N: Display,
     Ty: EdgeType,
     Ix: IndexType,
-    NP: NodeProjector,
 {
-    fn draw_svg(&self, get_api: fn() -> Box<dyn DrawingApi>, node_projector: &NP, file: &String) {
-        let mut draw = get_api();
-        let x_limits = (0, draw.get_area_width());
-        let y_limits = (0, draw.get_area_height());
-
-        let node_radius = std::cmp::min(x_limits.1, y_limits.1) / 4 / (self.node_count() as u32);
-
-        let usize_indices = utils::argsort(self.node_identifiers().clone());
-        let points = usize_indices
-            .into_iter()
-            .map(|(k, v)| {
-                let (i, small) = (k, node_projector.project(v, self.node_count()));
-                let big = drawing_api::scale(small.0, small.1, &x_limits, &y_limits);
-                (i, big)
-            })
-            .collect::<HashMap<_, _>>();
-
-        for (node, pos) in points.iter() {
-            for neighbor in self.neighbors(*node) {
-                let start = &points[node];
-                let end = &points[&neighbor];
-                draw.draw_line(start, end);
-            }
-            draw.draw_circle(pos, node_radius);
+    fn to_view(&self) -> GraphView {
+        let indices = utils::argsort(self.node_identifiers());
+        let mut labels = vec![String::new(); indices.len()];
+        for (node, i) in indices.iter() {
+            labels[*i] = self[*node].to_string();
         }
-
-        draw.export_svg(file);
+        let edges = self
+            .edge_references()
+            .map(|e| (indices[&e.source()], indices[&e.target()]))
+            .collect();
+        GraphView { labels, edges }
     }
 }
 
-impl ConstructGraph<NodeIndex<usize>> for Graph<(), (), Undirected, usize> {
+impl ConstructGraph<NodeIndex<usize>> for Graph<usize, (), Undirected, usize> {
     fn new(nodes: usize, edges: usize) -> Self {
         Graph::with_capacity(nodes, edges)
     }
 
-    fn add_node(&mut self) -> NodeIndex<usize> {
-        self.add_node(())
+    fn add_node(&mut self, label: usize) -> NodeIndex<usize> {
+        self.add_node(label)
     }
 
     fn add_edge(&mut self, from: NodeIndex<usize>, to: NodeIndex<usize>) {
@@ -63,9 +51,9 @@ impl ConstructGraph<NodeIndex<usize>> for Graph<(), (), Undirected, usize> {
     }
 }
 
-impl ReadGraph for Graph<(), (), Undirected, usize> {
-    fn read_from_str(s: &String) -> Result<Self, String> {
-       read_graph::<petgraph::prelude::NodeIndex<usize>, Graph<(), (), Undirected, usize>>(s)
+impl ReadGraph for Graph<usize, (), Undirected, usize> {
+    fn read_from_str(s: &str) -> Result<Self, String> {
+        read_graph::<petgraph::prelude::NodeIndex<usize>, Graph<usize, (), Undirected, usize>>(s)
     }
 }
 
@@ -81,19 +69,23 @@ mod tests {
 
     #[test]
     pub fn read_empty() {
-        read_graph::<NodeIndex<usize>, Graph<(), (), Undirected, usize>>(&EMPTY_GRAPH.to_string())
-            .unwrap();
+        read_graph::<NodeIndex<usize>, Graph<usize, (), Undirected, usize>>(EMPTY_GRAPH).unwrap();
     }
 
     #[test]
     fn read_one() {
-        read_graph::<NodeIndex<usize>, Graph<(), (), Undirected, usize>>(&ONE_EDGE.to_string())
-            .unwrap();
+        read_graph::<NodeIndex<usize>, Graph<usize, (), Undirected, usize>>(ONE_EDGE).unwrap();
     }
 
     #[test]
     fn read_triangle() {
-        read_graph::<NodeIndex<usize>, Graph<(), (), Undirected, usize>>(&TRIANGLE.to_string())
-            .unwrap();
+        read_graph::<NodeIndex<usize>, Graph<usize, (), Undirected, usize>>(TRIANGLE).unwrap();
+    }
+
+    #[test]
+    fn read_malformed() {
+        for input in ["", "3", "3\n1\n1", "3\n1\n1 2 3", "3\n1\n1 x"] {
+            read_graph::<NodeIndex<usize>, Graph<usize, (), Undirected, usize>>(input).unwrap_err();
+        }
     }
 }
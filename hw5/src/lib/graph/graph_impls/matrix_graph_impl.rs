@@ -1,77 +1,68 @@
-use std::collections::HashMap;
+use std::fmt::Display;
 
 use petgraph::{
     matrix_graph::{MatrixGraph, Nullable},
     prelude::NodeIndex,
     stable_graph::IndexType,
-    visit::IntoNodeIdentifiers,
+    visit::{EdgeRef, IntoEdgeReferences, IntoNodeIdentifiers},
     EdgeType, Undirected,
 };
 
 use crate::{
-    drawing_api::{self, DrawingApi},
-    graph::traits::{DrawGraph, NodeProjector, ReadGraph},
+    graph::{
+        traits::ReadGraph,
+        view::{GraphView, ToGraphView},
+    },
     utils,
 };
 
 use super::{read_graph, ConstructGraph};
 
-impl<N, E, Ty, Ix, Null, NP> DrawGraph<NP> for MatrixGraph<N, E, Ty, Null, Ix>
+impl<N, E, Ty, Ix, Null> ToGraphView for MatrixGraph<N, E, Ty, Null, Ix>
 where
+    N: Display,
     Ty: EdgeType,
     Ix: IndexType,
     Null: Nullable<Wrapped = E>,
-    NP: NodeProjector,
 {
-    fn draw_svg(&self, get_api: fn() -> Box<dyn DrawingApi>, node_projector: &NP, file: &String) {
-        let mut draw = get_api();
-        let x_limits = (0, draw.get_area_width());
-        let y_limits = (0, draw.get_area_height());
-
-        let node_radius = std::cmp::min(x_limits.1, y_limits.1) / 4 / (self.node_count() as u32);
-
-        let usize_indices = utils::argsort(self.node_identifiers().clone());
-        let points = usize_indices
-            .into_iter()
-            .map(|(k, v)| {
-                let (i, small) = (k, node_projector.project(v, self.node_count()));
-                let big = drawing_api::scale(small.0, small.1, &x_limits, &y_limits);
-                (i, big)
-            })
-            .collect::<HashMap<_, _>>();
-
-        for (node, pos) in points.iter() {
-            for neighbor in self.neighbors(*node) {
-                let start = &points[node];
-                let end = &points[&neighbor];
-                draw.draw_line(start, end);
-            }
-            draw.draw_circle(pos, node_radius);
+    fn to_view(&self) -> GraphView {
+        let indices = utils::argsort(self.node_identifiers());
+        let mut labels = vec![String::new(); indices.len()];
+        for (node, i) in indices.iter() {
+            labels[*i] = self[*node].to_string();
         }
-
-        draw.export_svg(file);
+        let edges = self
+            .edge_references()
+            .map(|e| (indices[&e.source()], indices[&e.target()]))
+            .collect();
+        GraphView { labels, edges }
     }
 }
 
-impl ConstructGraph<NodeIndex<usize>> for MatrixGraph<(), (), Undirected, Option<()>, usize> {
+impl ConstructGraph<NodeIndex<usize>> for MatrixGraph<usize, (), Undirected, Option<()>, usize> {
     fn new(nodes: usize, _edges: usize) -> Self {
         MatrixGraph::with_capacity(nodes)
     }
 
-    fn add_node(&mut self) -> NodeIndex<usize> {
-        self.add_node(())
+    fn add_node(&mut self, label: usize) -> NodeIndex<usize> {
+        self.add_node(label)
     }
 
     fn add_edge(&mut self, from: NodeIndex<usize>, to: NodeIndex<usize>) {
         self.add_edge(from, to, ());
     }
+
+    /// Matrices hold one edge between two nodes
+    fn has_edge(&self, from: NodeIndex<usize>, to: NodeIndex<usize>) -> bool {
+        MatrixGraph::has_edge(self, from, to)
+    }
 }
 
-impl ReadGraph for MatrixGraph<(), (), Undirected, Option<()>, usize> {
-    fn read_from_str(s: &String) -> Result<Self, String> {
+impl ReadGraph for MatrixGraph<usize, (), Undirected, Option<()>, usize> {
+    fn read_from_str(s: &str) -> Result<Self, String> {
         read_graph::<
             petgraph::matrix_graph::NodeIndex<usize>,
-            MatrixGraph<(), (), Undirected, Option<()>, usize>,
+            MatrixGraph<usize, (), Undirected, Option<()>, usize>,
         >(s)
     }
 }
@@ -88,9 +79,22 @@ pub mod matrix_graph_tests {
 
     #[test]
     pub fn read_triangle() {
-        read_graph::<NodeIndex<usize>, MatrixGraph<(), (), Undirected, Option<()>, usize>>(
-            &TRIANGLE.to_string(),
+        read_graph::<NodeIndex<usize>, MatrixGraph<usize, (), Undirected, Option<()>, usize>>(
+            TRIANGLE,
         )
         .unwrap();
     }
+
+    #[test]
+    fn read_malformed() {
+        let read =
+            read_graph::<NodeIndex<usize>, MatrixGraph<usize, (), Undirected, Option<()>, usize>>;
+        for input in ["3\n1\n1 x", "3\n2\n1 2\n1 2", "3\n3\n1 2\n2 3\n2 1"] {
+            assert!(read(input).is_err(), "{}", input);
+        }
+        assert_eq!(
+            Err("line 5: duplicate edge \"2 1\"".to_string()),
+            read("3\n3\n1 2\n2 3\n2 1").map(|_| ())
+        );
+    }
 }
@@ -1,6 +1,13 @@
-use std::{collections::HashMap, str::FromStr};
+use std::collections::HashMap;
 
-use super::traits::ConstructGraph;
+use crate::drawing_api::DrawingApi;
+
+use super::{
+    layout::{self, LayoutHints, Positions},
+    render::{self, RenderOptions},
+    traits::{ConstructGraph, DrawGraph, Layout},
+    view::ToGraphView,
+};
 
 pub mod list_graph_impl;
 pub mod matrix_graph_impl;
@@ -11,42 +18,96 @@ pub mod matrix_graph_impl;
 /// <edge1>
 /// <edge2>
 /// ... where <edge_i> = "<from> <to>" (without quotes)
-pub fn read_graph<Ix, G>(s: &String) -> Result<G, String>
+pub fn read_graph<Ix, G>(s: &str) -> Result<G, String>
 where
     Ix: Clone,
     G: ConstructGraph<Ix>,
 {
-    let lines = s.split("\n").filter(|s| !s.is_empty()).collect::<Vec<_>>();
-    (|| {
-        let n: usize = lines[0].parse()?;
-        let m: usize = lines[1].parse()?;
-        let mut graph = G::new(n, m);
-
-        let edges = lines
-            .into_iter()
-            .skip(2)
-            .map(|line| {
-                line.split(" ")
-                    .map(|s| s.parse::<usize>())
-                    .collect::<Result<Vec<_>, _>>()
-                    .map(|vec| (vec[0], vec[1]))
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let mut nodes = HashMap::<usize, _>::default();
-        for edge in edges.iter() {
-            if !nodes.contains_key(&edge.0) {
-                nodes.insert(edge.0, graph.add_node());
-            }
-            if !nodes.contains_key(&edge.1) {
-                nodes.insert(edge.1, graph.add_node());
+    let lines = s
+        .split('\n')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    let parse_count = |i: usize, what: &str| -> Result<usize, String> {
+        let line = lines
+            .get(i)
+            .ok_or_else(|| format!("missing number of {}", what))?;
+        line.parse()
+            .map_err(|e| format!("line {}: bad number of {}: {}", i + 1, what, e))
+    };
+    let n = parse_count(0, "nodes")?;
+    let m = parse_count(1, "edges")?;
+    let mut graph = G::new(n, m);
+
+    let edges = lines
+        .iter()
+        .enumerate()
+        .skip(2)
+        .map(|(i, line)| {
+            let ends = line
+                .split_whitespace()
+                .map(|s| s.parse::<usize>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("line {}: {}", i + 1, e))?;
+            match ends[..] {
+                [from, to] => Ok((from, to)),
+                _ => Err(format!(
+                    "line {}: expected \"<from> <to>\", found \"{}\"",
+                    i + 1,
+                    line
+                )),
             }
-            graph.add_edge(nodes[&edge.0].clone(), nodes[&edge.1].clone());
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut nodes = HashMap::<usize, _>::default();
+    for (i, edge) in edges.iter().enumerate() {
+        let from = nodes
+            .entry(edge.0)
+            .or_insert_with(|| graph.add_node(edge.0))
+            .clone();
+        let to = nodes
+            .entry(edge.1)
+            .or_insert_with(|| graph.add_node(edge.1))
+            .clone();
+        if graph.has_edge(from.clone(), to.clone()) {
+            return Err(format!(
+                "line {}: duplicate edge \"{} {}\"",
+                i + 3,
+                edge.0,
+                edge.1
+            ));
         }
+        graph.add_edge(from, to);
+    }
 
-        Ok(graph)
-    })()
-    .map_err(|e: <usize as FromStr>::Err| format!("{}", e))
+    Ok(graph)
+}
+
+impl<G, L> DrawGraph<L> for G
+where
+    G: ToGraphView,
+    L: Layout,
+{
+    fn draw_svg_with(
+        &self,
+        get_api: &dyn Fn() -> Result<Box<dyn DrawingApi>, String>,
+        layout: &L,
+        hints: &LayoutHints,
+        options: &RenderOptions,
+        file: &str,
+    ) -> Result<Positions, String> {
+        let view = match &options.subgraph {
+            Some(subgraph) => subgraph.extract(&self.to_view())?,
+            None => self.to_view(),
+        };
+        let points = layout.layout(&view, hints);
+
+        let mut draw = get_api()?;
+        render::draw_view(&view, &points, options, draw.as_mut());
+        draw.export(file)?;
+        Ok(layout::to_positions(&view, &points))
+    }
 }
 
 mod common {
@@ -0,0 +1,202 @@
+use crate::drawing_api::Point;
+
+/// How node labels are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LabelMode {
+    #[default]
+    None,
+    /// To the right of every node, even over other labels
+    Plain,
+    /// Around the node where the label doesn't cover other labels and nodes,
+    /// labels without such place are hidden
+    Placed,
+}
+
+/// Axis aligned box in canvas coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rect {
+    left: f32,
+    top: f32,
+    right: f32,
+    bottom: f32,
+}
+
+impl Rect {
+    fn intersects(&self, other: &Rect) -> bool {
+        self.left < other.right
+            && other.left < self.right
+            && self.top < other.bottom
+            && other.top < self.bottom
+    }
+
+    fn inside(&self, width: u32, height: u32) -> bool {
+        self.left >= 0.0
+            && self.top >= 0.0
+            && self.right <= width as f32
+            && self.bottom <= height as f32
+    }
+}
+
+/// Label with the left end of its baseline at `position`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlacedLabel {
+    pub node: usize,
+    pub position: Point,
+}
+
+/// Approximate size of a text, glyphs of sans-serif fonts are about 0.6 of font size wide
+fn text_size(text: &str, font_size: u32) -> (f32, f32) {
+    (
+        text.chars().count() as f32 * font_size as f32 * 0.6,
+        font_size as f32,
+    )
+}
+
+/// Box of a label placed next to a node in direction (dx, dy), both in -1..=1
+fn candidate(center: &Point, radius: u32, size: (f32, f32), dx: f32, dy: f32) -> Rect {
+    let gap = radius as f32 + 2.0;
+    let (cx, cy) = (center.x as f32 + dx * gap, center.y as f32 + dy * gap);
+    // the box touches the node with its side or corner facing it
+    let left = cx - size.0 * (1.0 - dx) / 2.0;
+    let top = cy - size.1 * (1.0 - dy) / 2.0;
+    Rect {
+        left,
+        top,
+        right: left + size.0,
+        bottom: top + size.1,
+    }
+}
+
+/// Positions for `labels` of nodes at `points` with radius `radius`.
+/// Nodes with bigger `priority` are labelled first and win the collisions.
+pub fn place_labels(
+    mode: LabelMode,
+    labels: &[String],
+    points: &[Point],
+    radius: u32,
+    font_size: u32,
+    priority: &[usize],
+    area: (u32, u32),
+) -> Vec<PlacedLabel> {
+    const DIRECTIONS: [(f32, f32); 8] = [
+        (1.0, 0.0),
+        (-1.0, 0.0),
+        (0.0, -1.0),
+        (0.0, 1.0),
+        (1.0, -1.0),
+        (-1.0, -1.0),
+        (1.0, 1.0),
+        (-1.0, 1.0),
+    ];
+    let to_placed = |node: usize, rect: Rect| PlacedLabel {
+        node,
+        position: Point {
+            x: rect.left.max(0.0) as u32,
+            y: rect.bottom.max(0.0) as u32,
+        },
+    };
+
+    match mode {
+        LabelMode::None => vec![],
+        LabelMode::Plain => (0..labels.len())
+            .map(|i| {
+                let size = text_size(&labels[i], font_size);
+                to_placed(i, candidate(&points[i], radius, size, 1.0, 0.0))
+            })
+            .collect(),
+        LabelMode::Placed => {
+            let mut taken = points
+                .iter()
+                .map(|p| Rect {
+                    left: p.x as f32 - radius as f32,
+                    top: p.y as f32 - radius as f32,
+                    right: p.x as f32 + radius as f32,
+                    bottom: p.y as f32 + radius as f32,
+                })
+                .collect::<Vec<_>>();
+            let mut order = (0..labels.len()).collect::<Vec<_>>();
+            order.sort_by_key(|i| std::cmp::Reverse(priority[*i]));
+
+            let mut placed = vec![];
+            for i in order {
+                let size = text_size(&labels[i], font_size);
+                let free = DIRECTIONS
+                    .iter()
+                    .map(|(dx, dy)| candidate(&points[i], radius, size, *dx, *dy))
+                    .find(|rect| {
+                        rect.inside(area.0, area.1) && !taken.iter().any(|t| t.intersects(rect))
+                    });
+                if let Some(rect) = free {
+                    taken.push(rect);
+                    placed.push(to_placed(i, rect));
+                }
+            }
+            placed.sort_by_key(|l| l.node);
+            placed
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("node{}", i)).collect()
+    }
+
+    #[test]
+    fn plain_labels_everything() {
+        let points = [Point { x: 50, y: 50 }, Point { x: 52, y: 50 }];
+        let placed = place_labels(
+            LabelMode::Plain,
+            &labels(2),
+            &points,
+            5,
+            10,
+            &[0, 0],
+            (200, 200),
+        );
+        assert_eq!(2, placed.len());
+        assert_eq!(Point { x: 57, y: 55 }, placed[0].position);
+    }
+
+    #[test]
+    fn placed_labels_move_away() {
+        let points = [Point { x: 100, y: 100 }, Point { x: 100, y: 108 }];
+        let placed = place_labels(
+            LabelMode::Placed,
+            &labels(2),
+            &points,
+            5,
+            10,
+            &[1, 0],
+            (200, 200),
+        );
+        assert_eq!(2, placed.len());
+        // the first one takes the right side, the second can't go there too
+        assert_eq!(Point { x: 107, y: 105 }, placed[0].position);
+        assert!(placed[1].position.x < 100);
+    }
+
+    #[test]
+    fn placed_labels_hide() {
+        // nodes are packed too tight for any label
+        let points = (0..9)
+            .map(|i| Point {
+                x: 10 + (i % 3) * 10,
+                y: 10 + (i / 3) * 10,
+            })
+            .collect::<Vec<_>>();
+        let placed = place_labels(
+            LabelMode::Placed,
+            &labels(9),
+            &points,
+            5,
+            10,
+            &[0; 9],
+            (40, 40),
+        );
+        assert!(placed.len() < 9);
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::{BTreeMap, HashSet};
+
+use super::{
+    node_projectors::CircularProjector,
+    traits::{Layout, NodeProjector},
+    view::GraphView,
+};
+
+/// Node positions in -1:1 square by node label
+pub type Positions = BTreeMap<String, (f32, f32)>;
+
+#[derive(Debug, Clone, Default)]
+pub struct LayoutHints {
+    /// Known positions to start from
+    pub start: Positions,
+    /// Labels of nodes that stay at their start positions
+    pub pinned: HashSet<String>,
+}
+
+/// Format of parsing:
+/// <label> <x> <y>
+/// ... one line per node, lines starting with '#' are comments
+pub fn read_positions(s: &str) -> Result<Positions, String> {
+    let mut positions = Positions::new();
+    for (i, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        let [label, x, y] = parts[..] else {
+            return Err(format!(
+                "line {}: expected \"<label> <x> <y>\", found \"{}\"",
+                i + 1,
+                line
+            ));
+        };
+        let coordinate = |c: &str| {
+            c.parse::<f32>()
+                .map_err(|e| format!("line {}: bad coordinate '{}': {}", i + 1, c, e))
+        };
+        positions.insert(label.to_string(), (coordinate(x)?, coordinate(y)?));
+    }
+    Ok(positions)
+}
+
+pub fn write_positions(positions: &Positions) -> String {
+    let mut out = "# label x y\n".to_string();
+    for (label, (x, y)) in positions.iter() {
+        out.push_str(format!("{} {} {}\n", label, x, y).as_str());
+    }
+    out
+}
+
+/// Collects positions returned by `Layout::layout` by labels of `view`
+pub fn to_positions(view: &GraphView, points: &[(f32, f32)]) -> Positions {
+    view.labels
+        .iter()
+        .cloned()
+        .zip(points.iter().cloned())
+        .collect()
+}
+
+/// Keeps nodes with known start positions there and projects the rest
+impl<NP: NodeProjector> Layout for NP {
+    fn layout(&self, view: &GraphView, hints: &LayoutHints) -> Vec<(f32, f32)> {
+        let n = view.node_count();
+        (0..n)
+            .map(|i| match hints.start.get(&view.labels[i]) {
+                Some(pos) => *pos,
+                None => self.project(i, n),
+            })
+            .collect()
+    }
+}
+
+impl Layout for Box<dyn Layout> {
+    fn layout(&self, view: &GraphView, hints: &LayoutHints) -> Vec<(f32, f32)> {
+        self.as_ref().layout(view, hints)
+    }
+}
+
+/// Fruchterman-Reingold spring embedder.
+/// Adjacent nodes attract each other, all the others repel.
+pub struct ForceLayout {
+    pub iterations: usize,
+    /// Max distance a node moves at the first iteration, it cools down to 0 at the last
+    pub temperature: f32,
+    /// How strongly nodes with start positions are pulled back to them,
+    /// relative to the pull of an edge
+    pub anchor: f32,
+}
+
+impl ForceLayout {
+    pub const ITERATIONS: usize = 300;
+    pub const TEMPERATURE: f32 = 0.2;
+    pub const ANCHOR: f32 = 1.0;
+
+    pub fn new() -> Self {
+        ForceLayout {
+            iterations: Self::ITERATIONS,
+            temperature: Self::TEMPERATURE,
+            anchor: Self::ANCHOR,
+        }
+    }
+}
+
+impl Default for ForceLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Layout for ForceLayout {
+    fn layout(&self, view: &GraphView, hints: &LayoutHints) -> Vec<(f32, f32)> {
+        let n = view.node_count();
+        let start = (0..n)
+            .map(|i| hints.start.get(&view.labels[i]).cloned())
+            .collect::<Vec<_>>();
+        let known = start.iter().flatten().count();
+        let mut pos = (0..n)
+            .map(|i| {
+                start[i].unwrap_or_else(|| {
+                    let (x, y) = CircularProjector.project(i, n);
+                    (x / 2.0, y / 2.0)
+                })
+            })
+            .collect::<Vec<_>>();
+        let movable = (0..n)
+            .map(|i| !hints.pinned.contains(&view.labels[i]))
+            .collect::<Vec<_>>();
+        if n < 2 {
+            return pos;
+        }
+
+        // a known layout only needs to make room for the new nodes
+        let new_share = (n - known) as f32 / n as f32;
+        let temperature = self.temperature * new_share.max(0.1);
+        // optimal distance between nodes for the square of area 4
+        let k = (4.0 / n as f32).sqrt() / 2.0;
+
+        for iteration in 0..self.iterations {
+            let mut disp = vec![(0.0f32, 0.0f32); n];
+            for i in 0..n {
+                for j in (i + 1)..n {
+                    let (dx, dy, dist) = delta(pos[i], pos[j], i, j);
+                    let force = k * k / dist;
+                    disp[i].0 += dx / dist * force;
+                    disp[i].1 += dy / dist * force;
+                    disp[j].0 -= dx / dist * force;
+                    disp[j].1 -= dy / dist * force;
+                }
+            }
+            for &(a, b) in view.edges.iter().filter(|(a, b)| a != b) {
+                let (dx, dy, dist) = delta(pos[a], pos[b], a, b);
+                let force = dist * dist / k;
+                disp[a].0 -= dx / dist * force;
+                disp[a].1 -= dy / dist * force;
+                disp[b].0 += dx / dist * force;
+                disp[b].1 += dy / dist * force;
+            }
+            for i in 0..n {
+                if let Some(anchor) = start[i] {
+                    let (dx, dy) = (pos[i].0 - anchor.0, pos[i].1 - anchor.1);
+                    let dist = (dx * dx + dy * dy).sqrt();
+                    disp[i].0 -= dx * dist / k * self.anchor;
+                    disp[i].1 -= dy * dist / k * self.anchor;
+                }
+            }
+
+            let t = temperature * (1.0 - iteration as f32 / self.iterations as f32);
+            for i in (0..n).filter(|i| movable[*i]) {
+                let (dx, dy) = disp[i];
+                let len = (dx * dx + dy * dy).sqrt();
+                if len > 0.0 {
+                    let step = len.min(t) / len;
+                    pos[i].0 = (pos[i].0 + dx * step).clamp(-1.0, 1.0);
+                    pos[i].1 = (pos[i].1 + dy * step).clamp(-1.0, 1.0);
+                }
+            }
+        }
+        pos
+    }
+}
+
+/// Vector from `b` to `a` and its length.
+/// Nodes at the same point are pushed apart in a direction depending on their indices.
+fn delta(a: (f32, f32), b: (f32, f32), i: usize, j: usize) -> (f32, f32, f32) {
+    let (dx, dy) = (a.0 - b.0, a.1 - b.1);
+    let dist = (dx * dx + dy * dy).sqrt();
+    if dist > 1e-4 {
+        return (dx, dy, dist);
+    }
+    let angle = (i * 31 + j * 17) as f32;
+    (angle.cos() * 1e-4, angle.sin() * 1e-4, 1e-4)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn square() -> GraphView {
+        GraphView {
+            labels: ["1", "2", "3", "4"].iter().map(|s| s.to_string()).collect(),
+            edges: vec![(0, 1), (1, 2), (2, 3), (3, 0)],
+        }
+    }
+
+    #[test]
+    fn positions_round_trip() {
+        let positions = read_positions("# comment\n1 0.5 -0.25\n\n10 -1 1\n").unwrap();
+        assert_eq!(Some(&(0.5, -0.25)), positions.get("1"));
+        assert_eq!(
+            positions,
+            read_positions(&write_positions(&positions)).unwrap()
+        );
+    }
+
+    #[test]
+    fn positions_malformed() {
+        read_positions("1 0.5").unwrap_err();
+        read_positions("1 0.5 y").unwrap_err();
+    }
+
+    #[test]
+    fn projector_keeps_start() {
+        let view = square();
+        let hints = LayoutHints {
+            start: [("3".to_string(), (0.1, 0.2))].into_iter().collect(),
+            pinned: HashSet::new(),
+        };
+        let points = CircularProjector.layout(&view, &hints);
+        assert_eq!((0.1, 0.2), points[2]);
+        assert_eq!(CircularProjector.project(0, 4), points[0]);
+    }
+
+    #[test]
+    fn force_pins_nodes() {
+        let view = square();
+        let hints = LayoutHints {
+            start: [("1".to_string(), (0.9, 0.9))].into_iter().collect(),
+            pinned: ["1".to_string()].into_iter().collect(),
+        };
+        let points = ForceLayout::new().layout(&view, &hints);
+        assert_eq!((0.9, 0.9), points[0]);
+        for (x, y) in points {
+            assert!((-1.0..=1.0).contains(&x) && (-1.0..=1.0).contains(&y));
+        }
+    }
+
+    #[test]
+    fn force_separates_nodes() {
+        let view = square();
+        let points = ForceLayout::new().layout(&view, &LayoutHints::default());
+        for i in 0..4 {
+            for j in (i + 1)..4 {
+                let (dx, dy) = (points[i].0 - points[j].0, points[i].1 - points[j].1);
+                assert!((dx * dx + dy * dy).sqrt() > 0.1);
+            }
+        }
+    }
+}
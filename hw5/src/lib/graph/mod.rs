@@ -1,3 +1,10 @@
 pub mod traits;
 pub mod node_projectors;
 pub mod graph_impls;
+pub mod stats;
+pub mod layout;
+pub mod view;
+pub mod bundling;
+pub mod labels;
+pub mod render;
+pub mod subgraph;
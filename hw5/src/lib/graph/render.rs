@@ -0,0 +1,70 @@
+use crate::drawing_api::{self, DrawingApi};
+
+use super::{
+    bundling::EdgeBundling,
+    labels::{self, LabelMode},
+    subgraph::Subgraph,
+    view::GraphView,
+};
+
+/// Optional passes of drawing, all are off by default
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Bundle edges and draw them as curves
+    pub bundling: Option<EdgeBundling>,
+    pub labels: LabelMode,
+    /// Lay out and draw only this part of the graph
+    pub subgraph: Option<Subgraph>,
+}
+
+/// Draws edges, nodes and labels of `view` placed at `points` of -1:1 square
+pub fn draw_view(
+    view: &GraphView,
+    points: &[(f32, f32)],
+    options: &RenderOptions,
+    draw: &mut dyn DrawingApi,
+) {
+    let x_limits = (0, draw.get_area_width());
+    let y_limits = (0, draw.get_area_height());
+    let scale = |(x, y): &(f32, f32)| drawing_api::scale(*x, *y, &x_limits, &y_limits);
+
+    let node_radius =
+        std::cmp::min(x_limits.1, y_limits.1) / 4 / std::cmp::max(view.node_count() as u32, 1);
+
+    let scaled = points.iter().map(scale).collect::<Vec<_>>();
+
+    match &options.bundling {
+        Some(bundling) => {
+            for line in bundling.bundle(points, &view.edges) {
+                draw.draw_curve(&line.iter().map(scale).collect::<Vec<_>>());
+            }
+        }
+        None => {
+            for (from, to) in view.edges.iter() {
+                draw.draw_line(&scaled[*from], &scaled[*to]);
+            }
+        }
+    }
+    for pos in scaled.iter() {
+        draw.draw_circle(pos, node_radius);
+    }
+
+    let font_size = node_radius.clamp(10, 24);
+    let mut degrees = vec![0; view.node_count()];
+    for (a, b) in view.edges.iter() {
+        degrees[*a] += 1;
+        degrees[*b] += 1;
+    }
+    let placed = labels::place_labels(
+        options.labels,
+        &view.labels,
+        &scaled,
+        node_radius,
+        font_size,
+        &degrees,
+        (x_limits.1, y_limits.1),
+    );
+    for label in placed {
+        draw.draw_text(&label.position, &view.labels[label.node], font_size);
+    }
+}
@@ -0,0 +1,80 @@
+use petgraph::{stable_graph::IndexType, visit::Dfs, Graph, Undirected};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphStats {
+    pub nodes: usize,
+    pub edges: usize,
+    pub components: usize,
+    pub max_degree: usize,
+    /// edges / max possible edges, 0 for graphs with less than 2 nodes
+    pub density: f32,
+}
+
+impl GraphStats {
+    pub fn of<N, E, Ix: IndexType>(graph: &Graph<N, E, Undirected, Ix>) -> Self {
+        let nodes = graph.node_count();
+        let edges = graph.edge_count();
+
+        let mut components = 0;
+        let mut visited = vec![false; nodes];
+        for start in graph.node_indices() {
+            if visited[start.index()] {
+                continue;
+            }
+            components += 1;
+            let mut dfs = Dfs::new(graph, start);
+            while let Some(node) = dfs.next(graph) {
+                visited[node.index()] = true;
+            }
+        }
+
+        let max_degree = graph
+            .node_indices()
+            .map(|node| graph.edges(node).count())
+            .max()
+            .unwrap_or(0);
+
+        let density = if nodes < 2 {
+            0.0
+        } else {
+            2.0 * edges as f32 / (nodes * (nodes - 1)) as f32
+        };
+
+        GraphStats {
+            nodes,
+            edges,
+            components,
+            max_degree,
+            density,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::{Graph, Undirected};
+
+    use super::GraphStats;
+    use crate::graph::traits::ReadGraph;
+
+    #[test]
+    fn triangle_and_edge() {
+        let graph =
+            Graph::<usize, (), Undirected, usize>::read_from_str("5\n4\n1 2\n2 3\n1 3\n4 5")
+                .unwrap();
+        let stats = GraphStats::of(&graph);
+        assert_eq!(5, stats.nodes);
+        assert_eq!(4, stats.edges);
+        assert_eq!(2, stats.components);
+        assert_eq!(2, stats.max_degree);
+        assert!((stats.density - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn empty() {
+        let graph = Graph::<usize, (), Undirected, usize>::read_from_str("0\n0\n").unwrap();
+        let stats = GraphStats::of(&graph);
+        assert_eq!(0, stats.components);
+        assert_eq!(0.0, stats.density);
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::VecDeque;
+
+use super::view::GraphView;
+
+/// Part of a graph that is laid out and drawn instead of the whole graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Subgraph {
+    /// Nodes at most `hops` edges away from `center`
+    Ego { center: String, hops: usize },
+    /// Given nodes and the edges between them
+    Induced(Vec<String>),
+    /// Connected component with the most nodes, the first one of equal size
+    LargestComponent,
+}
+
+impl Subgraph {
+    pub fn extract(&self, view: &GraphView) -> Result<GraphView, String> {
+        let find = |label: &str| {
+            view.index_of(label)
+                .ok_or_else(|| format!("no node '{}' in the graph", label))
+        };
+        let keep = match self {
+            Subgraph::Ego { center, hops } => distances(view, find(center)?)
+                .into_iter()
+                .map(|d| d.is_some_and(|d| d <= *hops))
+                .collect(),
+            Subgraph::Induced(labels) => {
+                let mut keep = vec![false; view.node_count()];
+                for label in labels {
+                    keep[find(label)?] = true;
+                }
+                keep
+            }
+            Subgraph::LargestComponent => {
+                let component = components(view);
+                let mut sizes = vec![0; view.node_count()];
+                for c in component.iter() {
+                    sizes[*c] += 1;
+                }
+                // max_by_key returns the last maximum, so the reversed order finds the first
+                let largest = (0..sizes.len()).rev().max_by_key(|c| sizes[*c]);
+                component.iter().map(|c| Some(*c) == largest).collect()
+            }
+        };
+        Ok(induced(view, &keep))
+    }
+}
+
+fn neighbors(view: &GraphView) -> Vec<Vec<usize>> {
+    let mut neighbors = vec![vec![]; view.node_count()];
+    for (a, b) in view.edges.iter() {
+        neighbors[*a].push(*b);
+        neighbors[*b].push(*a);
+    }
+    neighbors
+}
+
+/// Edges from `start` to every node, `None` for unreachable ones
+fn distances(view: &GraphView, start: usize) -> Vec<Option<usize>> {
+    let neighbors = neighbors(view);
+    let mut distance = vec![None; view.node_count()];
+    distance[start] = Some(0);
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        let next = distance[node].map(|d| d + 1);
+        for n in neighbors[node].iter() {
+            if distance[*n].is_none() {
+                distance[*n] = next;
+                queue.push_back(*n);
+            }
+        }
+    }
+    distance
+}
+
+/// Component of every node, components are numbered by their smallest node
+fn components(view: &GraphView) -> Vec<usize> {
+    let neighbors = neighbors(view);
+    let mut component = vec![usize::MAX; view.node_count()];
+    for start in 0..view.node_count() {
+        if component[start] != usize::MAX {
+            continue;
+        }
+        component[start] = start;
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            for n in neighbors[node].iter() {
+                if component[*n] == usize::MAX {
+                    component[*n] = start;
+                    stack.push(*n);
+                }
+            }
+        }
+    }
+    component
+}
+
+/// Nodes with `keep` set and edges between them, nodes keep their order
+fn induced(view: &GraphView, keep: &[bool]) -> GraphView {
+    let mut index = vec![None; view.node_count()];
+    let mut labels = vec![];
+    for (i, label) in view.labels.iter().enumerate() {
+        if keep[i] {
+            index[i] = Some(labels.len());
+            labels.push(label.clone());
+        }
+    }
+    let edges = view
+        .edges
+        .iter()
+        .filter_map(|(a, b)| Some((index[*a]?, index[*b]?)))
+        .collect();
+    GraphView { labels, edges }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// path 1-2-3-4 and edge 5-6
+    fn two_components() -> GraphView {
+        GraphView {
+            labels: (1..=6).map(|i| i.to_string()).collect(),
+            edges: vec![(0, 1), (1, 2), (2, 3), (4, 5)],
+        }
+    }
+
+    fn labels(view: &GraphView) -> Vec<&str> {
+        view.labels.iter().map(|l| l.as_str()).collect()
+    }
+
+    #[test]
+    fn ego() {
+        let ego = |center: &str, hops| Subgraph::Ego {
+            center: center.to_string(),
+            hops,
+        };
+        let view = ego("2", 1).extract(&two_components()).unwrap();
+        assert_eq!(vec!["1", "2", "3"], labels(&view));
+        assert_eq!(vec![(0, 1), (1, 2)], view.edges);
+
+        let view = ego("1", 0).extract(&two_components()).unwrap();
+        assert_eq!(vec!["1"], labels(&view));
+        assert!(view.edges.is_empty());
+        assert!(ego("7", 1).extract(&two_components()).is_err());
+    }
+
+    #[test]
+    fn induced_by_labels() {
+        let subgraph = Subgraph::Induced(vec!["4".to_string(), "3".to_string(), "5".to_string()]);
+        let view = subgraph.extract(&two_components()).unwrap();
+        assert_eq!(vec!["3", "4", "5"], labels(&view));
+        assert_eq!(vec![(0, 1)], view.edges);
+    }
+
+    #[test]
+    fn largest_component() {
+        let view = Subgraph::LargestComponent
+            .extract(&two_components())
+            .unwrap();
+        assert_eq!(vec!["1", "2", "3", "4"], labels(&view));
+        assert_eq!(3, view.edges.len());
+
+        let empty = Subgraph::LargestComponent
+            .extract(&GraphView::default())
+            .unwrap();
+        assert_eq!(GraphView::default(), empty);
+    }
+}
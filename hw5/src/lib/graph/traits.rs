@@ -1,7 +1,33 @@
 use crate::drawing_api::DrawingApi;
 
-pub trait DrawGraph<NP: NodeProjector> {
-    fn draw_svg(&self, get_api: fn() -> Box<dyn DrawingApi>, node_projector: &NP, file: &String);
+use super::{
+    layout::{LayoutHints, Positions},
+    render::RenderOptions,
+    view::GraphView,
+};
+
+pub trait DrawGraph<L: Layout> {
+    fn draw_svg(
+        &self,
+        get_api: &dyn Fn() -> Result<Box<dyn DrawingApi>, String>,
+        layout: &L,
+        file: &str,
+    ) -> Result<(), String> {
+        let hints = LayoutHints::default();
+        self.draw_svg_with(get_api, layout, &hints, &RenderOptions::default(), file)
+            .map(|_| ())
+    }
+
+    /// Same as `draw_svg`, but starts from the layout in `hints` and draws as `options` say.
+    /// Returns positions the nodes were drawn at.
+    fn draw_svg_with(
+        &self,
+        get_api: &dyn Fn() -> Result<Box<dyn DrawingApi>, String>,
+        layout: &L,
+        hints: &LayoutHints,
+        options: &RenderOptions,
+        file: &str,
+    ) -> Result<Positions, String>;
 }
 
 /// Projects nodes on float plane with axis of length 1.0
@@ -15,12 +41,24 @@ impl NodeProjector for Box<dyn NodeProjector> {
     }
 }
 
+/// Places all nodes of a graph at once on the same plane as `NodeProjector`
+pub trait Layout {
+    /// Returns position of every node of `view`.
+    /// Pinned nodes of `hints` must stay at their start positions.
+    fn layout(&self, view: &GraphView, hints: &LayoutHints) -> Vec<(f32, f32)>;
+}
+
 pub trait ConstructGraph<Ix: Clone> {
     fn new(nodes: usize, edges: usize) -> Self;
-    fn add_node(&mut self) -> Ix;
+    fn add_node(&mut self, label: usize) -> Ix;
     fn add_edge(&mut self, from: Ix, to: Ix);
+    /// Whether the graph can't take another edge between the nodes,
+    /// graphs with parallel edges always can
+    fn has_edge(&self, _from: Ix, _to: Ix) -> bool {
+        false
+    }
 }
 
 pub trait ReadGraph: Sized {
-    fn read_from_str(s: &String) -> Result<Self, String>;
+    fn read_from_str(s: &str) -> Result<Self, String>;
 }
@@ -0,0 +1,21 @@
+/// Representation independent structure of a graph,
+/// nodes are numbered `0..labels.len()`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GraphView {
+    pub labels: Vec<String>,
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl GraphView {
+    pub fn node_count(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn index_of(&self, label: &str) -> Option<usize> {
+        self.labels.iter().position(|l| l == label)
+    }
+}
+
+pub trait ToGraphView {
+    fn to_view(&self) -> GraphView;
+}
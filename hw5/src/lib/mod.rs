@@ -1,4 +1,7 @@
 pub mod drawing_api;
 pub mod graph;
 pub mod apis;
+pub mod gallery;
+pub mod timeline;
+pub mod registry;
 mod utils;
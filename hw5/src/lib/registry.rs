@@ -0,0 +1,541 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{
+    apis::{
+        draw::Draw,
+        pdf::{PageSize, Pdf, Rgb},
+        simple_svg::SimpleSvg,
+    },
+    drawing_api::DrawingApi,
+    graph::{
+        layout::ForceLayout,
+        node_projectors::{ArchimedeanSpiralProjector, CircularProjector},
+        traits::Layout,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionKind {
+    Int,
+    Float,
+    Bool,
+    Text,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Text(String),
+}
+
+impl OptionValue {
+    pub fn kind(&self) -> OptionKind {
+        match self {
+            OptionValue::Int(_) => OptionKind::Int,
+            OptionValue::Float(_) => OptionKind::Float,
+            OptionValue::Bool(_) => OptionKind::Bool,
+            OptionValue::Text(_) => OptionKind::Text,
+        }
+    }
+
+    fn parse(kind: OptionKind, s: &str) -> Result<Self, String> {
+        match kind {
+            OptionKind::Int => s.parse().map(OptionValue::Int).map_err(|e| e.to_string()),
+            OptionKind::Float => s.parse().map(OptionValue::Float).map_err(|e| e.to_string()),
+            OptionKind::Bool => s.parse().map(OptionValue::Bool).map_err(|e| e.to_string()),
+            OptionKind::Text => Ok(OptionValue::Text(s.to_string())),
+        }
+    }
+}
+
+impl Display for OptionValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptionValue::Int(x) => write!(f, "{}", x),
+            OptionValue::Float(x) => write!(f, "{}", x),
+            OptionValue::Bool(x) => write!(f, "{}", x),
+            OptionValue::Text(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+/// Option a backend or a layout accepts, the kind is the kind of the default value
+#[derive(Debug, Clone)]
+pub struct OptionSpec {
+    pub name: String,
+    pub default: OptionValue,
+    pub description: String,
+    /// Default as it's listed
+    shown: String,
+}
+
+impl OptionSpec {
+    pub fn new(name: &str, default: OptionValue, description: &str) -> Self {
+        OptionSpec {
+            name: name.to_string(),
+            shown: default.to_string(),
+            default,
+            description: description.to_string(),
+        }
+    }
+
+    /// Float option used as f32, its default is listed as the shortest f32 decimal,
+    /// 0.2 rather than 0.20000000298023224
+    pub fn float(name: &str, default: f32, description: &str) -> Self {
+        OptionSpec {
+            shown: default.to_string(),
+            ..OptionSpec::new(name, OptionValue::Float(default.into()), description)
+        }
+    }
+
+    pub fn shown_default(&self) -> &str {
+        &self.shown
+    }
+
+    pub fn kind(&self) -> OptionKind {
+        self.default.kind()
+    }
+}
+
+/// Values of all options of a backend or a layout, already checked against their specs
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    values: BTreeMap<String, OptionValue>,
+}
+
+impl Options {
+    fn get(&self, name: &str) -> Result<&OptionValue, String> {
+        self.values
+            .get(name)
+            .ok_or_else(|| format!("option '{}' isn't declared", name))
+    }
+
+    fn mismatch(name: &str, value: &OptionValue, kind: OptionKind) -> String {
+        format!("option '{}' is {:?}, not {:?}", name, value.kind(), kind)
+    }
+
+    /// Errors if a factory asks for an option it didn't declare or for another kind
+    pub fn int(&self, name: &str) -> Result<i64, String> {
+        match self.get(name)? {
+            OptionValue::Int(x) => Ok(*x),
+            other => Err(Self::mismatch(name, other, OptionKind::Int)),
+        }
+    }
+
+    pub fn float(&self, name: &str) -> Result<f64, String> {
+        match self.get(name)? {
+            OptionValue::Float(x) => Ok(*x),
+            other => Err(Self::mismatch(name, other, OptionKind::Float)),
+        }
+    }
+
+    pub fn bool(&self, name: &str) -> Result<bool, String> {
+        match self.get(name)? {
+            OptionValue::Bool(x) => Ok(*x),
+            other => Err(Self::mismatch(name, other, OptionKind::Bool)),
+        }
+    }
+
+    pub fn text(&self, name: &str) -> Result<&str, String> {
+        match self.get(name)? {
+            OptionValue::Text(x) => Ok(x),
+            other => Err(Self::mismatch(name, other, OptionKind::Text)),
+        }
+    }
+}
+
+pub type BackendFactory =
+    Box<dyn Fn(u32, u32, &Options) -> Result<Box<dyn DrawingApi>, String> + Send + Sync>;
+pub type LayoutFactory = Box<dyn Fn(&Options) -> Result<Box<dyn Layout>, String> + Send + Sync>;
+
+pub struct Entry<F> {
+    pub name: String,
+    pub description: String,
+    pub options: Vec<OptionSpec>,
+    factory: F,
+}
+
+impl<F> Entry<F> {
+    /// Defaults of all options overridden by `raw` "name" -> "value" pairs
+    pub fn parse_options(&self, raw: &[(String, String)]) -> Result<Options, String> {
+        let mut values = self
+            .options
+            .iter()
+            .map(|spec| (spec.name.clone(), spec.default.clone()))
+            .collect::<BTreeMap<_, _>>();
+        for (name, value) in raw {
+            let spec = self
+                .options
+                .iter()
+                .find(|spec| spec.name == *name)
+                .ok_or_else(|| format!("'{}' has no option '{}'", self.name, name))?;
+            let value = OptionValue::parse(spec.kind(), value).map_err(|e| {
+                format!(
+                    "bad value '{}' of '{}' of '{}': {}",
+                    value, name, self.name, e
+                )
+            })?;
+            values.insert(name.clone(), value);
+        }
+        Ok(Options { values })
+    }
+}
+
+/// Backends and layouts by name.
+/// Library users can register their own next to the built-in ones.
+#[derive(Default)]
+pub struct Registry {
+    backends: BTreeMap<String, Entry<BackendFactory>>,
+    layouts: BTreeMap<String, Entry<LayoutFactory>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    /// Registry with the backends and layouts of this crate
+    pub fn with_builtins() -> Self {
+        let mut registry = Registry::new();
+        registry
+            .register_builtins()
+            .expect("built-in names are unique");
+        registry
+    }
+
+    fn register_builtins(&mut self) -> Result<(), String> {
+        self.register_backend(
+            "simple-svg",
+            "SVG with simplesvg",
+            vec![],
+            Box::new(|width, height, _| Ok(Box::new(SimpleSvg::new(width, height)))),
+        )?;
+        self.register_backend(
+            "draw",
+            "SVG with draw",
+            vec![],
+            Box::new(|width, height, _| Ok(Box::new(Draw::new(width, height)))),
+        )?;
+        let text = |name: &str, default: &str, description: &str| {
+            OptionSpec::new(name, OptionValue::Text(default.to_string()), description)
+        };
+        self.register_backend(
+            "pdf",
+            "Vector PDF",
+            vec![
+                text("page", "canvas", "canvas, a3, a4, a5, letter or legal"),
+                OptionSpec::new("landscape", OptionValue::Bool(false), "Turn the page"),
+                OptionSpec::float("margin", 36.0, "Margin of paper pages in points"),
+                text("line-color", "#323232", "Colour of edges"),
+                text("node-color", "#000000", "Colour of nodes"),
+                text("text-color", "#000000", "Colour of labels"),
+            ],
+            Box::new(|width, height, options| {
+                let mut pdf = Pdf::new(width, height);
+                pdf.page = PageSize::from_name(options.text("page")?)?;
+                pdf.landscape = options.bool("landscape")?;
+                pdf.margin = options.float("margin")? as f32;
+                pdf.line_color = Rgb::parse(options.text("line-color")?)?;
+                pdf.node_color = Rgb::parse(options.text("node-color")?)?;
+                pdf.text_color = Rgb::parse(options.text("text-color")?)?;
+                Ok(Box::new(pdf))
+            }),
+        )?;
+
+        self.register_layout(
+            "circle",
+            "Nodes on a circle",
+            vec![],
+            Box::new(|_| Ok(Box::new(CircularProjector))),
+        )?;
+        self.register_layout(
+            "spiral",
+            "Nodes on an Archimedean spiral",
+            vec![
+                OptionSpec::float("a", 10.0, "Radius the spiral starts at"),
+                OptionSpec::float("turns", 1.4, "Turns of the spiral"),
+            ],
+            Box::new(|options| {
+                Ok(Box::new(ArchimedeanSpiralProjector::new(
+                    options.float("a")? as f32,
+                    options.float("turns")? as f32,
+                )))
+            }),
+        )?;
+        self.register_layout(
+            "force",
+            "Fruchterman-Reingold spring embedder",
+            vec![
+                OptionSpec::new(
+                    "iterations",
+                    OptionValue::Int(ForceLayout::ITERATIONS as i64),
+                    "Steps of the simulation",
+                ),
+                OptionSpec::float(
+                    "temperature",
+                    ForceLayout::TEMPERATURE,
+                    "Max move of a node at the first step",
+                ),
+                OptionSpec::float(
+                    "anchor",
+                    ForceLayout::ANCHOR,
+                    "Pull of nodes to their start positions",
+                ),
+            ],
+            Box::new(|options| {
+                let iterations = options.int("iterations")?;
+                if iterations < 0 {
+                    return Err("iterations can't be negative".to_string());
+                }
+                Ok(Box::new(ForceLayout {
+                    iterations: iterations as usize,
+                    temperature: options.float("temperature")? as f32,
+                    anchor: options.float("anchor")? as f32,
+                }))
+            }),
+        )
+    }
+
+    pub fn register_backend(
+        &mut self,
+        name: &str,
+        description: &str,
+        options: Vec<OptionSpec>,
+        factory: BackendFactory,
+    ) -> Result<(), String> {
+        register(
+            &mut self.backends,
+            "backend",
+            name,
+            description,
+            options,
+            factory,
+        )
+    }
+
+    pub fn register_layout(
+        &mut self,
+        name: &str,
+        description: &str,
+        options: Vec<OptionSpec>,
+        factory: LayoutFactory,
+    ) -> Result<(), String> {
+        register(
+            &mut self.layouts,
+            "layout",
+            name,
+            description,
+            options,
+            factory,
+        )
+    }
+
+    /// Registered backends sorted by name
+    pub fn backends(&self) -> impl Iterator<Item = &Entry<BackendFactory>> {
+        self.backends.values()
+    }
+
+    /// Registered layouts sorted by name
+    pub fn layouts(&self) -> impl Iterator<Item = &Entry<LayoutFactory>> {
+        self.layouts.values()
+    }
+
+    pub fn backend(
+        &self,
+        name: &str,
+        width: u32,
+        height: u32,
+        options: &[(String, String)],
+    ) -> Result<Box<dyn DrawingApi>, String> {
+        let entry = find(&self.backends, "backend", name)?;
+        (entry.factory)(width, height, &entry.parse_options(options)?)
+    }
+
+    pub fn layout(
+        &self,
+        name: &str,
+        options: &[(String, String)],
+    ) -> Result<Box<dyn Layout>, String> {
+        let entry = find(&self.layouts, "layout", name)?;
+        (entry.factory)(&entry.parse_options(options)?)
+    }
+}
+
+fn register<F>(
+    entries: &mut BTreeMap<String, Entry<F>>,
+    what: &str,
+    name: &str,
+    description: &str,
+    options: Vec<OptionSpec>,
+    factory: F,
+) -> Result<(), String> {
+    if entries.contains_key(name) {
+        return Err(format!("{} '{}' is already registered", what, name));
+    }
+    entries.insert(
+        name.to_string(),
+        Entry {
+            name: name.to_string(),
+            description: description.to_string(),
+            options,
+            factory,
+        },
+    );
+    Ok(())
+}
+
+fn find<'a, F>(
+    entries: &'a BTreeMap<String, Entry<F>>,
+    what: &str,
+    name: &str,
+) -> Result<&'a Entry<F>, String> {
+    entries.get(name).ok_or_else(|| {
+        let known = entries.keys().cloned().collect::<Vec<_>>();
+        format!(
+            "unknown {} '{}', registered: {}",
+            what,
+            name,
+            known.join(", ")
+        )
+    })
+}
+
+/// Splits "name=value" of command line options
+pub fn parse_option(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .ok_or_else(|| format!("expected \"name=value\", found \"{}\"", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(options: &[(&str, &str)]) -> Vec<(String, String)> {
+        options
+            .iter()
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn builtins() {
+        let registry = Registry::with_builtins();
+        let backends = registry
+            .backends()
+            .map(|e| e.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["draw", "pdf", "simple-svg"], backends);
+        let layouts = registry
+            .layouts()
+            .map(|e| e.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["circle", "force", "spiral"], layouts);
+
+        let api = registry.backend("draw", 100, 200, &[]).unwrap();
+        assert_eq!(200, api.get_area_height());
+        registry
+            .layout("spiral", &raw(&[("turns", "2.5")]))
+            .unwrap();
+    }
+
+    #[test]
+    fn bad_options() {
+        let registry = Registry::with_builtins();
+        assert!(registry.layout("spiral", &raw(&[("turns", "x")])).is_err());
+        assert!(registry.layout("spiral", &raw(&[("loops", "1")])).is_err());
+        assert!(registry
+            .layout("force", &raw(&[("iterations", "-1")]))
+            .is_err());
+        assert!(registry.layout("tree", &[]).is_err());
+        let pdf =
+            |option: &str, value: &str| registry.backend("pdf", 10, 10, &raw(&[(option, value)]));
+        assert!(pdf("page", "a4").is_ok());
+        assert!(pdf("page", "b5").is_err());
+        assert!(pdf("node-color", "red").is_err());
+    }
+
+    #[test]
+    fn custom_layout() {
+        struct Diagonal;
+        impl Layout for Diagonal {
+            fn layout(
+                &self,
+                view: &crate::graph::view::GraphView,
+                _: &crate::graph::layout::LayoutHints,
+            ) -> Vec<(f32, f32)> {
+                (0..view.node_count())
+                    .map(|i| (i as f32, i as f32))
+                    .collect()
+            }
+        }
+
+        let mut registry = Registry::with_builtins();
+        registry
+            .register_layout(
+                "diagonal",
+                "Nodes on a diagonal",
+                vec![OptionSpec::new("flip", OptionValue::Bool(false), "")],
+                Box::new(|options| {
+                    assert!(options.bool("flip")?);
+                    Ok(Box::new(Diagonal))
+                }),
+            )
+            .unwrap();
+        assert!(registry
+            .register_layout("diagonal", "", vec![], Box::new(|_| Ok(Box::new(Diagonal))))
+            .is_err());
+        registry
+            .layout("diagonal", &raw(&[("flip", "true")]))
+            .unwrap();
+    }
+
+    #[test]
+    fn undeclared_options() {
+        let mut registry = Registry::new();
+        registry
+            .register_layout(
+                "circle",
+                "",
+                vec![OptionSpec::new("r", OptionValue::Int(1), "")],
+                Box::new(|options| {
+                    options.int("r")?;
+                    options.float("r")?;
+                    Ok(Box::new(CircularProjector))
+                }),
+            )
+            .unwrap();
+        assert_eq!(
+            Some("option 'r' is Int, not Float".to_string()),
+            registry.layout("circle", &[]).err()
+        );
+        let options = Options::default();
+        assert_eq!(
+            Err("option 'turns' isn't declared".to_string()),
+            options.float("turns")
+        );
+    }
+
+    #[test]
+    fn listed_defaults() {
+        let registry = Registry::with_builtins();
+        let force = registry.layouts().find(|e| e.name == "force").unwrap();
+        let defaults = force
+            .options
+            .iter()
+            .map(|spec| spec.shown_default())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["300", "0.2", "1"], defaults);
+    }
+
+    #[test]
+    fn parse_options() {
+        assert_eq!(
+            ("a".to_string(), "1.5".to_string()),
+            parse_option("a = 1.5").unwrap()
+        );
+        assert!(parse_option("a").is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{
+    drawing_api::{self, Point},
+    graph::{
+        layout::{self, LayoutHints},
+        traits::Layout,
+        view::GraphView,
+    },
+};
+
+/// Lays out every step starting from the positions of the previous one,
+/// so nodes shared by consecutive steps stay where they were
+pub fn layout_steps(
+    steps: &[GraphView],
+    layout: &dyn Layout,
+    hints: &LayoutHints,
+) -> Vec<Vec<(f32, f32)>> {
+    let mut hints = hints.clone();
+    let mut result = vec![];
+    for view in steps {
+        let points = layout.layout(view, &hints);
+        hints.start.extend(layout::to_positions(view, &points));
+        result.push(points);
+    }
+    result
+}
+
+/// Where a node or an edge is at every step, `None` if it's absent
+type Track = Vec<Option<(Point, Point)>>;
+
+/// Renders all steps into one SVG animated with SMIL, each step is shown for `step_seconds`.
+/// `points[i]` are positions of nodes of `steps[i]` in -1:1 square.
+pub fn render_animated_svg(
+    steps: &[GraphView],
+    points: &[Vec<(f32, f32)>],
+    width: u32,
+    height: u32,
+    step_seconds: f32,
+) -> String {
+    let x_limits = (0, width);
+    let y_limits = (0, height);
+    let max_nodes = steps.iter().map(|s| s.node_count()).max().unwrap_or(0);
+    let node_radius = std::cmp::min(width, height) / 4 / std::cmp::max(max_nodes as u32, 1);
+
+    // nodes are tracked as zero length segments to share the code with edges
+    let mut nodes = BTreeMap::<String, Track>::new();
+    let mut edges = BTreeMap::<(String, String), Track>::new();
+    for (i, (view, points)) in steps.iter().zip(points.iter()).enumerate() {
+        let scaled = points
+            .iter()
+            .map(|(x, y)| drawing_api::scale(*x, *y, &x_limits, &y_limits))
+            .collect::<Vec<_>>();
+        for (label, p) in view.labels.iter().zip(scaled.iter()) {
+            let track = nodes
+                .entry(label.clone())
+                .or_insert_with(|| vec![None; steps.len()]);
+            track[i] = Some((*p, *p));
+        }
+        for (a, b) in view.edges.iter() {
+            let (mut a, mut b) = (*a, *b);
+            if view.labels[a] > view.labels[b] {
+                std::mem::swap(&mut a, &mut b);
+            }
+            let key = (view.labels[a].clone(), view.labels[b].clone());
+            let track = edges.entry(key).or_insert_with(|| vec![None; steps.len()]);
+            track[i] = Some((scaled[a], scaled[b]));
+        }
+    }
+
+    let animation = Animation {
+        steps: steps.len(),
+        seconds: step_seconds * steps.len() as f32,
+    };
+    let mut out = String::new();
+    // writing into a String never fails
+    let _ = write_animated_svg(
+        &mut out,
+        &animation,
+        &nodes,
+        &edges,
+        width,
+        height,
+        node_radius,
+    );
+    out
+}
+
+fn write_animated_svg(
+    out: &mut String,
+    animation: &Animation,
+    nodes: &BTreeMap<String, Track>,
+    edges: &BTreeMap<(String, String), Track>,
+    width: u32,
+    height: u32,
+    node_radius: u32,
+) -> std::fmt::Result {
+    writeln!(
+        out,
+        r#"<svg width="{0}" height="{1}" viewBox="0 0 {0} {1}" xmlns="http://www.w3.org/2000/svg">"#,
+        width, height
+    )?;
+    writeln!(out, r#"<g stroke="rgb(50,50,50)" stroke-width="5">"#)?;
+    for track in edges.values() {
+        let filled = fill_gaps(track);
+        let (from, to) = filled[0];
+        write!(
+            out,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}"{}>"#,
+            from.x,
+            from.y,
+            to.x,
+            to.y,
+            initial_visibility(track)
+        )?;
+        animation.write_coordinates(out, "x1", &filled, |s| s.0.x)?;
+        animation.write_coordinates(out, "y1", &filled, |s| s.0.y)?;
+        animation.write_coordinates(out, "x2", &filled, |s| s.1.x)?;
+        animation.write_coordinates(out, "y2", &filled, |s| s.1.y)?;
+        animation.write_visibility(out, track)?;
+        writeln!(out, "</line>")?;
+    }
+    writeln!(out, "</g>")?;
+    writeln!(out, r#"<g fill="rgb(0,0,0)">"#)?;
+    for track in nodes.values() {
+        let filled = fill_gaps(track);
+        let p = filled[0].0;
+        write!(
+            out,
+            r#"<circle cx="{}" cy="{}" r="{}"{}>"#,
+            p.x,
+            p.y,
+            node_radius,
+            initial_visibility(track)
+        )?;
+        animation.write_coordinates(out, "cx", &filled, |s| s.0.x)?;
+        animation.write_coordinates(out, "cy", &filled, |s| s.0.y)?;
+        animation.write_visibility(out, track)?;
+        writeln!(out, "</circle>")?;
+    }
+    writeln!(out, "</g>")?;
+    writeln!(out, "</svg>")
+}
+
+fn initial_visibility(track: &Track) -> &'static str {
+    match track[0] {
+        Some(_) => "",
+        None => r#" visibility="hidden""#,
+    }
+}
+
+/// Replaces absent steps with the closest earlier step, or the first present one.
+/// Hidden elements then don't jump around before appearing.
+fn fill_gaps(track: &Track) -> Vec<(Point, Point)> {
+    let mut last = *track
+        .iter()
+        .flatten()
+        .next()
+        .expect("every track has at least one step");
+    track
+        .iter()
+        .map(|p| {
+            if let Some(p) = p {
+                last = *p;
+            }
+            last
+        })
+        .collect()
+}
+
+struct Animation {
+    steps: usize,
+    seconds: f32,
+}
+
+impl Animation {
+    /// Every step takes equal share of the cycle, values switch without interpolation
+    fn write_animate(
+        &self,
+        out: &mut String,
+        attribute: &str,
+        values: &[String],
+    ) -> std::fmt::Result {
+        if values.windows(2).all(|w| w[0] == w[1]) {
+            return Ok(());
+        }
+        let key_times = (0..self.steps)
+            .map(|i| format!("{}", i as f32 / self.steps as f32))
+            .collect::<Vec<_>>();
+        write!(
+            out,
+            r#"<animate attributeName="{}" values="{}" keyTimes="{}" dur="{}s" calcMode="discrete" repeatCount="indefinite"/>"#,
+            attribute,
+            values.join(";"),
+            key_times.join(";"),
+            self.seconds
+        )
+    }
+
+    fn write_coordinates(
+        &self,
+        out: &mut String,
+        attribute: &str,
+        track: &[(Point, Point)],
+        coordinate: impl Fn(&(Point, Point)) -> u32,
+    ) -> std::fmt::Result {
+        let values = track
+            .iter()
+            .map(|s| coordinate(s).to_string())
+            .collect::<Vec<_>>();
+        self.write_animate(out, attribute, &values)
+    }
+
+    fn write_visibility(&self, out: &mut String, track: &Track) -> std::fmt::Result {
+        let values = track
+            .iter()
+            .map(|p| match p {
+                Some(_) => "visible".to_string(),
+                None => "hidden".to_string(),
+            })
+            .collect::<Vec<_>>();
+        self.write_animate(out, "visibility", &values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::node_projectors::CircularProjector;
+
+    fn view(labels: &[&str], edges: &[(usize, usize)]) -> GraphView {
+        GraphView {
+            labels: labels.iter().map(|s| s.to_string()).collect(),
+            edges: edges.to_vec(),
+        }
+    }
+
+    #[test]
+    fn shared_nodes_stay() {
+        let steps = [
+            view(&["1", "2"], &[(0, 1)]),
+            view(&["0", "1", "2"], &[(0, 1), (1, 2)]),
+        ];
+        let points = layout_steps(&steps, &CircularProjector, &LayoutHints::default());
+        assert_eq!(points[0][0], points[1][1]);
+        assert_eq!(points[0][1], points[1][2]);
+    }
+
+    #[test]
+    fn appearing_node_is_animated() {
+        let steps = [view(&["1"], &[]), view(&["1", "2"], &[(0, 1)])];
+        let points = layout_steps(&steps, &CircularProjector, &LayoutHints::default());
+        let svg = render_animated_svg(&steps, &points, 100, 100, 1.0);
+        assert_eq!(2, svg.matches("<circle").count());
+        assert_eq!(1, svg.matches("<line").count());
+        // node 2 and the edge are hidden at the first step
+        assert_eq!(2, svg.matches(r#"visibility="hidden""#).count());
+        assert_eq!(2, svg.matches(r#"values="hidden;visible""#).count());
+    }
+}
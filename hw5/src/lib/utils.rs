@@ -9,8 +9,8 @@ where
     sorted.sort();
 
     let mut ans: HashMap<T::Item, usize> = Default::default();
-    for i in 0..sorted.len() {
-        ans.insert(sorted[i].clone(), i);
+    for (i, element) in sorted.into_iter().enumerate() {
+        ans.insert(element, i);
     }
     ans
 }
@@ -0,0 +1,55 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use my_lib::{
+    bytecode::Compiled, environment::Environment, tokenizer::Tokenizer,
+    visitors::parser_visitor::ParserVisitor,
+};
+
+const FORMULA: &str = "x ^ 2 + 3 * x * y - sin(x) / (y + 1) + max(x, y, 1)";
+
+/// Variables x and y the formula is computed with, made before the benchmarks
+/// so that all of them take the same inputs
+fn environments() -> Vec<Environment<f64>> {
+    (0..1000)
+        .map(|i| {
+            let mut environment = Environment::new();
+            environment.set("x", i as f64 / 100.0).unwrap();
+            environment.set("y", 1.0 - i as f64 / 500.0).unwrap();
+            environment
+        })
+        .collect()
+}
+
+fn compare(c: &mut Criterion) {
+    let mut group = c.benchmark_group("1000 points");
+    // the formula assigns nothing, so the environments stay the same
+    let mut environments = environments();
+    group.bench_function("parse and calc", |b| {
+        b.iter(|| {
+            for environment in &mut environments {
+                let tokens = Tokenizer::tokenize_spanned(black_box(FORMULA)).unwrap();
+                let rpn = ParserVisitor::parse(tokens).unwrap();
+                black_box(environment.execute(rpn).unwrap());
+            }
+        })
+    });
+    let rpn = ParserVisitor::parse(Tokenizer::tokenize_spanned(FORMULA).unwrap()).unwrap();
+    group.bench_function("calc of parsed", |b| {
+        b.iter(|| {
+            for environment in &mut environments {
+                black_box(environment.execute(rpn.clone()).unwrap());
+            }
+        })
+    });
+    let compiled = Compiled::<f64>::compile(rpn).unwrap();
+    group.bench_function("compiled", |b| {
+        b.iter(|| {
+            for environment in &environments {
+                black_box(compiled.eval_in(environment).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, compare);
+criterion_main!(benches);
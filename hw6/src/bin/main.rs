@@ -1,24 +1,343 @@
+use std::{
+    fs,
+    io::{self, BufRead, IsTerminal},
+    path::PathBuf,
+    str::FromStr,
+};
+
 use my_lib::{
-    token::TokenListVisitor,
+    environment::Session,
+    error::Error,
+    number::Domain,
+    span::{Span, SpanError},
+    token::{Function, Token, TokenListVisitor},
     tokenizer::Tokenizer,
     visitors::{
-        calc_visitor::CalcVisitor, parser_visitor::ParserVisitor, print_visitor::PrintVisitor,
+        ast_visitor::AstVisitor,
+        parser_visitor::ParserVisitor,
+        print_visitor::{self, PrintVisitor},
     },
 };
-use text_io::read;
 
-fn main() {
-    let input: String = read!("{}\n");
-    let res = (|| -> Result<(), String> {
-        let tokens = Tokenizer::tokenize(input)?;
-        let rpn = ParserVisitor::visit_tokens(tokens)?;
-        PrintVisitor::visit_tokens(rpn.clone())?;
-        let val = CalcVisitor::visit_tokens(rpn)?;
-        println!("{}", val);
+use rustyline::{error::ReadlineError, DefaultEditor};
+
+const USAGE: &str = "Usage: todo_name [--domain int|float|rational|bigint] \
+                     [--overflow checked|saturating|wrapping] [--print rpn|infix|prefix] [file]\n\
+                     Without a file it reads the expressions from the terminal or stdin";
+
+const HELP: &str = "\
+<expression>         print the expression and its value, like 2 * (x + 1)
+<name> = <expr>      assign a variable
+<c> ? <a> : <b>      a if c is true else b, c from < <= > >= == != && || !
+diff <name> <expr>   print the derivative by the variable
+:rpn <expr>          print the expression in RPN
+:tree <expr>         print the expression tree
+:vars                print the variables and constants
+:help                print this
+:quit                end the session, like Ctrl-D
+A line ending with '\\' or with an unclosed '(' goes on in the next one";
+
+/// How the expression is printed before its value
+#[derive(Clone, Copy, Default)]
+enum Notation {
+    #[default]
+    Rpn,
+    /// With only the needed parentheses
+    Infix,
+    Prefix,
+}
+
+impl FromStr for Notation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rpn" => Ok(Notation::Rpn),
+            "infix" => Ok(Notation::Infix),
+            "prefix" => Ok(Notation::Prefix),
+            _ => Err(format!(
+                "Unknown notation '{}', expected rpn, infix or prefix",
+                s
+            )),
+        }
+    }
+}
+
+/// Settings of the command line
+struct Args {
+    domain: Domain,
+    notation: Notation,
+    /// File of expressions, one per line
+    file: Option<PathBuf>,
+}
+
+/// Number type from `--domain <name>` or `-d <name>`, rational by default.
+/// `--overflow <mode>` is only for the int domain.
+/// Notation from `--print <notation>` or `-p <notation>`, RPN by default.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut domain = Domain::default();
+    let mut notation = Notation::default();
+    let mut overflow = None;
+    let mut file = None;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value of {}\n{}", arg, USAGE))
+        };
+        match arg.as_str() {
+            "-d" | "--domain" => domain = value()?.parse()?,
+            "--overflow" => overflow = Some(value()?.parse()?),
+            "-p" | "--print" => notation = value()?.parse()?,
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if !arg.starts_with('-') && file.is_none() => file = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument '{}'\n{}", arg, USAGE)),
+        }
+    }
+    let domain = match (domain, overflow) {
+        (_, None) => domain,
+        (Domain::Int(_), Some(overflow)) => Domain::Int(overflow),
+        _ => return Err("--overflow is only for --domain int".to_string()),
+    };
+    Ok(Args {
+        domain,
+        notation,
+        file,
+    })
+}
+
+/// What a line asks for, the expression of it is kept apart
+enum Command<'a> {
+    Evaluate,
+    Diff(&'a str),
+    Rpn,
+    Tree,
+    Vars,
+    Help,
+    Quit,
+}
+
+/// Command of a line and its expression, with the byte where the expression starts
+fn parse_command(input: &str) -> Result<(Command<'_>, &str, usize), String> {
+    let trimmed = input.trim_start();
+    let (command, expression) = if let Some(name) = trimmed.strip_prefix(':') {
+        let end = name.find(char::is_whitespace).unwrap_or(name.len());
+        let (name, expression) = name.split_at(end);
+        let command = match name {
+            "rpn" => Command::Rpn,
+            "tree" => Command::Tree,
+            "vars" => Command::Vars,
+            "help" => Command::Help,
+            "q" | "quit" => Command::Quit,
+            _ => return Err(format!("Unknown command ':{}', see :help", name)),
+        };
+        (command, expression)
+    } else if let Some((variable, expression)) = diff_command(trimmed) {
+        (Command::Diff(variable), expression)
+    } else {
+        (Command::Evaluate, trimmed)
+    };
+    Ok((command, expression, input.len() - expression.len()))
+}
+
+/// Variable and the rest of a line `diff <variable> <expression>`
+fn diff_command(input: &str) -> Option<(&str, &str)> {
+    let rest = input.strip_prefix("diff")?;
+    let name = rest.trim_start();
+    if name.len() == rest.len() {
+        return None;
+    }
+    let end = name
+        .find(|c: char| !c.is_alphanumeric() && c != '_')
+        .unwrap_or(name.len());
+    let (variable, expression) = name.split_at(end);
+    if variable.is_empty()
+        || variable.starts_with(|c: char| c.is_ascii_digit())
+        || Function::from_name(variable).is_some()
+    {
+        return None;
+    }
+    Some((variable, expression))
+}
+
+/// RPN of an expression
+fn parse(input: &str) -> Result<Vec<(Token, Span)>, SpanError> {
+    ParserVisitor::parse(Tokenizer::tokenize_spanned(input)?)
+}
+
+/// Whether the line needs the next one: it ends with '\\', a '(' is open
+/// or an operand is missing at the end
+fn unfinished(input: &str) -> bool {
+    if input.ends_with('\\') {
+        return true;
+    }
+    let Ok((command, expression, _)) = parse_command(input) else {
+        return false;
+    };
+    match command {
+        Command::Evaluate | Command::Diff(_) | Command::Rpn | Command::Tree => {
+            let error = parse(expression).err().map(|e| e.error);
+            !expression.trim().is_empty()
+                && matches!(
+                    error,
+                    Some(Error::UnclosedParen | Error::MissingOperand(None))
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Variables of the session and the printing settings
+struct Calculator {
+    session: Box<dyn Session>,
+    notation: Notation,
+}
+
+impl Calculator {
+    /// Runs a command or an expression of one line, prints what it makes.
+    /// Errors show the line with '^' under the wrong part
+    fn run(&mut self, input: &str) -> Result<(), String> {
+        let (command, expression, offset) = parse_command(input)?;
+        self.run_command(command, expression).map_err(|e| {
+            SpanError {
+                span: e.span.map(|s| Span::new(s.start + offset, s.end + offset)),
+                ..e
+            }
+            .render(input)
+        })
+    }
+
+    fn run_command(&mut self, command: Command, input: &str) -> Result<(), SpanError> {
+        match command {
+            Command::Evaluate => {
+                let rpn = parse(input)?;
+                // the expression is shown and computed in one pass over the RPN
+                match self.notation {
+                    Notation::Rpn | Notation::Prefix => {
+                        let mut printer = PrintVisitor::with_notation(match self.notation {
+                            Notation::Prefix => print_visitor::Notation::Prefix,
+                            _ => print_visitor::Notation::Rpn,
+                        });
+                        let x = self.session.execute_with(rpn, &mut printer)?;
+                        println!("{}", printer.get_result()?);
+                        println!("{}", x);
+                    }
+                    Notation::Infix => {
+                        let mut tree = AstVisitor::new();
+                        let x = self.session.execute_with(rpn, &mut tree)?;
+                        println!("{}", tree.get_result()?.infix());
+                        println!("{}", x);
+                    }
+                }
+            }
+            Command::Diff(variable) => {
+                let expr = AstVisitor::visit_spanned(parse(input)?)?;
+                println!("{}", expr.derivative(variable)?.simplify());
+            }
+            Command::Rpn => println!("{}", PrintVisitor::visit_spanned(parse(input)?)?),
+            Command::Tree => println!("{}", AstVisitor::visit_spanned(parse(input)?)?.tree()),
+            Command::Vars => {
+                for (name, x) in self.session.variables() {
+                    println!("{} = {}", name, x);
+                }
+            }
+            Command::Help => println!("{}", HELP),
+            Command::Quit => {}
+        }
         Ok(())
+    }
+}
+
+/// File the history of the terminal sessions is kept in
+fn history_file() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".todo_name_history"))
+}
+
+/// Reads commands with line editing and history until `:quit` or the end of the input,
+/// errors are printed and the session goes on
+fn interactive(calculator: &mut Calculator) -> Result<(), String> {
+    let mut editor = DefaultEditor::new().map_err(|e| format!("Unable to start: {}", e))?;
+    let history = history_file();
+    if let Some(history) = &history {
+        // there's no history before the first session
+        let _ = editor.load_history(history);
+    }
+    let mut input = String::new();
+    loop {
+        let prompt = if input.is_empty() { "> " } else { "... " };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            // Ctrl-C drops the unfinished input
+            Err(ReadlineError::Interrupted) => {
+                input.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(format!("Unable to read the input: {}", e)),
+        };
+        let more = !line.trim().is_empty();
+        input = format!("{} {}", input.trim_end_matches('\\'), line);
+        input = input.trim().to_string();
+        if more && unfinished(&input) || input.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(&input);
+        if matches!(parse_command(&input), Ok((Command::Quit, _, _))) {
+            break;
+        }
+        if let Err(message) = calculator.run(&input) {
+            println!("Error: {}", message);
+        }
+        input.clear();
+    }
+    if let Some(history) = &history {
+        editor
+            .save_history(history)
+            .map_err(|e| format!("Unable to save the history: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Runs every line of `reader` until `:quit`, errors are printed with the line number.
+/// Returns whether all lines succeeded
+fn batch(calculator: &mut Calculator, reader: impl BufRead) -> Result<bool, String> {
+    let mut ok = true;
+    for (number, input) in reader.lines().enumerate() {
+        let input = input.map_err(|e| format!("Unable to read the input: {}", e))?;
+        if input.trim().is_empty() {
+            continue;
+        }
+        if matches!(parse_command(&input), Ok((Command::Quit, _, _))) {
+            break;
+        }
+        if let Err(message) = calculator.run(&input) {
+            println!("Error on line {}: {}", number + 1, message);
+            ok = false;
+        }
+    }
+    Ok(ok)
+}
+
+/// Evaluates the file from the arguments, or stdin: interactively if it's a terminal
+fn main() {
+    let res = (|| -> Result<bool, String> {
+        let args = parse_args(std::env::args().skip(1))?;
+        let mut calculator = Calculator {
+            session: args.domain.session(),
+            notation: args.notation,
+        };
+        match &args.file {
+            Some(path) => {
+                let file = fs::File::open(path)
+                    .map_err(|e| format!("Unable to open {}: {}", path.display(), e))?;
+                batch(&mut calculator, io::BufReader::new(file))
+            }
+            None if io::stdin().is_terminal() => interactive(&mut calculator).map(|()| true),
+            None => batch(&mut calculator, io::stdin().lock()),
+        }
     })();
     match res {
-        Ok(()) => {}
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
         Err(message) => {
             println!("Error: {}", message);
             std::process::exit(1);
@@ -0,0 +1,280 @@
+use crate::{
+    environment::Environment,
+    error::Error,
+    number::Number,
+    span::{Span, SpanError},
+    token::{Function, Operator, Token, TokenListVisitor},
+    value::Value,
+    visitors::compile_visitor::CompileVisitor,
+};
+
+/// One step of the stack machine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    /// Pushes the constant with the index
+    Const(usize),
+    /// Pushes the variable with the index
+    Load(usize),
+    Neg,
+    /// Checks that the value on the top is a number, unary plus does nothing else
+    Pos,
+    Not,
+    /// Replaces the two values on the top with the result of the operator
+    Binary(Operator),
+    /// Replaces the arguments on the top with the result of the function
+    Call(Function, usize),
+    /// Jumps to the index if the boolean on the top decides `&&` or `||`,
+    /// else pops it for the right operand to take its place
+    ShortCircuit(Operator, usize),
+    /// Checks that the right operand of `&&` or `||` on the top is a boolean
+    Boolean(Operator),
+    /// Pops the condition of `?:`, jumps to the index if it's false
+    JumpUnless(usize),
+    Jump(usize),
+}
+
+/// Expression compiled once to be computed with many values of its variables
+#[derive(Debug, Clone)]
+pub struct Compiled<N> {
+    pub(crate) code: Vec<Instruction>,
+    /// Span of the token of every instruction
+    pub(crate) spans: Vec<Span>,
+    pub(crate) constants: Vec<N>,
+    pub(crate) variables: Vec<String>,
+    pub(crate) target: Option<String>,
+    /// Most values on the stack at once
+    pub(crate) depth: usize,
+}
+
+const CHECKED: &str = "stack is checked by the compiler";
+
+impl<N: Number> Compiled<N> {
+    /// Compiles RPN `tokens` made by `ParserVisitor::parse`
+    pub fn compile(tokens: Vec<(Token, Span)>) -> Result<Self, SpanError> {
+        CompileVisitor::visit_spanned(tokens)
+    }
+
+    pub fn code(&self) -> &[Instruction] {
+        &self.code
+    }
+
+    /// Names of the variables in the order `eval` takes their values
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    /// Variable of the assignment, it's not assigned by `eval`
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    /// Computes the expression with `values` of `variables()`
+    pub fn eval(&self, values: &[N]) -> Result<Value<N>, SpanError> {
+        self.check_values(values.len())?;
+        self.run(|k| Value::Number(values[k].clone()))
+    }
+
+    /// Computes the expression with the variables of `environment`
+    pub fn eval_in(&self, environment: &Environment<N>) -> Result<Value<N>, SpanError> {
+        let values = self
+            .variables
+            .iter()
+            .enumerate()
+            .map(|(k, name)| {
+                environment.lookup(name).cloned().map_err(|error| {
+                    let load = self.code.iter().position(|i| *i == Instruction::Load(k));
+                    let error = SpanError::from(error);
+                    match load {
+                        Some(i) => error.or_at(self.spans[i]),
+                        None => error,
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.run(|k| values[k].clone())
+    }
+
+    fn check_values(&self, count: usize) -> Result<(), SpanError> {
+        if count != self.variables.len() {
+            return Err(Error::invalid(&format!(
+                "Expected {} variable values, got {}",
+                self.variables.len(),
+                count
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Runs the code with `load` giving the value of the variable with an index
+    fn run(&self, load: impl Fn(usize) -> Value<N>) -> Result<Value<N>, SpanError> {
+        let mut stack: Vec<Value<N>> = Vec::with_capacity(self.depth);
+        let mut i = 0;
+        while i < self.code.len() {
+            let span = self.spans[i];
+            let at = |error| SpanError::new(error, span);
+            let mut pop = || stack.pop().expect(CHECKED);
+            let x = match &self.code[i] {
+                Instruction::Const(k) => Value::Number(self.constants[*k].clone()),
+                Instruction::Load(k) => load(*k),
+                Instruction::Neg => Value::unary(&Operator::UnaryMinus, pop()).map_err(at)?,
+                Instruction::Pos => Value::unary(&Operator::UnaryPlus, pop()).map_err(at)?,
+                Instruction::Not => Value::unary(&Operator::Not, pop()).map_err(at)?,
+                Instruction::Binary(op) => {
+                    let x2 = pop();
+                    let x1 = pop();
+                    Value::binary(op, x1, x2).map_err(at)?
+                }
+                Instruction::Call(function, args) => {
+                    let args = stack.split_off(stack.len() - args);
+                    Value::call(*function, args).map_err(at)?
+                }
+                Instruction::ShortCircuit(op, to) => {
+                    match Value::short_circuit(op, pop()).map_err(at)? {
+                        Some(x) => {
+                            stack.push(x);
+                            i = *to;
+                        }
+                        None => i += 1,
+                    }
+                    continue;
+                }
+                Instruction::Boolean(op) => {
+                    let what = format!("Operand of '{}'", op);
+                    Value::Bool(pop().into_bool(&what).map_err(at)?)
+                }
+                Instruction::JumpUnless(to) => {
+                    let condition = pop().into_bool("Condition of '?'").map_err(at)?;
+                    i = if condition { i + 1 } else { *to };
+                    continue;
+                }
+                Instruction::Jump(to) => {
+                    i = *to;
+                    continue;
+                }
+            };
+            stack.push(x);
+            i += 1;
+        }
+        Ok(stack.pop().expect(CHECKED))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Compiled, Instruction};
+    use crate::{
+        environment::Environment,
+        error::Error,
+        number::Number,
+        span::{Span, SpanError},
+        token::{Function, Operator},
+        tokenizer::Tokenizer,
+        value::Value,
+        visitors::parser_visitor::ParserVisitor,
+    };
+
+    fn compile<N: Number>(input: &str) -> Result<Compiled<N>, SpanError> {
+        let tokens = Tokenizer::tokenize_spanned(input)?;
+        Compiled::compile(ParserVisitor::parse(tokens)?)
+    }
+
+    #[test]
+    fn code() {
+        let compiled = compile::<i32>("-x * max(y, 2, x)").unwrap();
+        assert_eq!(["x", "y"], compiled.variables());
+        assert_eq!(
+            [
+                Instruction::Load(0),
+                Instruction::Neg,
+                Instruction::Load(1),
+                Instruction::Const(0),
+                Instruction::Load(0),
+                Instruction::Call(Function::Max, 3),
+                Instruction::Binary(Operator::Star),
+            ],
+            compiled.code()
+        );
+        assert_eq!(Ok(Value::Number(-15)), compiled.eval(&[3, 5]));
+        assert_eq!(Ok(Value::Number(-4)), compiled.eval(&[2, -1]));
+    }
+
+    #[test]
+    fn same_as_calc() {
+        let mut environment = Environment::<f64>::with_constants();
+        for input in [
+            "x ^ 2 + 3 * x * y - sin(x) / (y + 1)",
+            "log(2, x) + pi",
+            "+x % 3",
+            "x > y || y == 0 ? x : y < 0 ? -y : 0",
+            "!(x < 1) && y != 2",
+            "+(x < y)",
+            "-(x > y)",
+        ] {
+            let compiled = compile::<f64>(input).unwrap();
+            for (x, y) in [(1.0, 2.0), (0.5, -3.0), (8.0, 0.0)] {
+                environment.set("x", x).unwrap();
+                environment.set("y", y).unwrap();
+                let tokens = ParserVisitor::parse(Tokenizer::tokenize_spanned(input).unwrap());
+                let expected = environment.clone().execute(tokens.unwrap());
+                assert_eq!(expected, compiled.eval_in(&environment));
+            }
+        }
+    }
+
+    #[test]
+    fn jumps() {
+        let compiled = compile::<i32>("x != 0 && 6 / x > 2 ? x : -1").unwrap();
+        assert_eq!(
+            [
+                Instruction::Load(0),
+                Instruction::Const(0),
+                Instruction::Binary(Operator::NotEqual),
+                Instruction::ShortCircuit(Operator::And, 10),
+                Instruction::Const(1),
+                Instruction::Load(0),
+                Instruction::Binary(Operator::Slash),
+                Instruction::Const(2),
+                Instruction::Binary(Operator::Greater),
+                Instruction::Boolean(Operator::And),
+                Instruction::JumpUnless(13),
+                Instruction::Load(0),
+                Instruction::Jump(14),
+                Instruction::Const(3),
+            ],
+            compiled.code()
+        );
+        assert_eq!(Ok(Value::Number(1)), compiled.eval(&[1]));
+        assert_eq!(Ok(Value::Number(-1)), compiled.eval(&[0]));
+        assert_eq!(Ok(Value::Number(-1)), compiled.eval(&[4]));
+        assert_eq!(
+            Ok(Value::Bool(true)),
+            compile::<i32>("x || 1 / 0 > 1").unwrap().eval_in(&{
+                let mut environment = Environment::new();
+                environment.set("x", Value::Bool(true)).unwrap();
+                environment
+            })
+        );
+        let error = compile::<i32>("x ? 1 : 2").unwrap().eval(&[1]).unwrap_err();
+        assert_eq!(Some(Span::new(2, 3)), error.span);
+    }
+
+    #[test]
+    fn errors() {
+        let compiled = compile::<i32>("a = 1 / (x - 1)").unwrap();
+        assert_eq!(Some("a"), compiled.target());
+        assert_eq!(
+            Err(SpanError::new(Error::DivisionByZero, Span::new(6, 7))),
+            compiled.eval(&[1])
+        );
+        assert!(compiled.eval(&[]).is_err());
+        assert_eq!(
+            Err(SpanError::new(
+                Error::UnknownVariable("x".to_string()),
+                Span::new(9, 10)
+            )),
+            compiled.eval_in(&Environment::new())
+        );
+        assert!(compile::<i32>("99999999999 + x").is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    error::Error,
+    number::Number,
+    span::{Span, SpanError},
+    token::{Literal, Token, TokenListVisitor},
+    value::Value,
+    visitors::{
+        calc_visitor::CalcVisitor,
+        combinators::{DynVisitor, Sink},
+    },
+};
+
+/// Names that can't be assigned and their values
+pub const CONSTANTS: [(&str, &str); 2] = [
+    ("pi", "3.14159265358979323846264338327950288"),
+    ("e", "2.71828182845904523536028747135266250"),
+];
+
+fn is_constant(name: &str) -> bool {
+    CONSTANTS.iter().any(|(constant, _)| *constant == name)
+}
+
+/// Values of variables, kept between statements
+#[derive(Debug, Clone)]
+pub struct Environment<N> {
+    variables: BTreeMap<String, Value<N>>,
+}
+
+impl<N: Number> Environment<N> {
+    /// No variables, not even the constants
+    pub fn new() -> Self {
+        Environment {
+            variables: BTreeMap::new(),
+        }
+    }
+
+    /// Constants that `N` can hold, integers have none
+    pub fn with_constants() -> Self {
+        let mut environment = Environment::new();
+        for (name, value) in CONSTANTS {
+            let literal = Literal::parse(value).expect("constants are valid literals");
+            if let Ok(x) = N::from_literal(&literal) {
+                environment.variables.insert(name.to_string(), x.into());
+            }
+        }
+        environment
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value<N>> {
+        self.variables.get(name)
+    }
+
+    /// Value of `name`, the error tells a constant `N` can't hold from an unknown variable
+    pub fn lookup(&self, name: &str) -> Result<&Value<N>, Error> {
+        self.get(name).ok_or_else(|| match is_constant(name) {
+            true => Error::UnavailableConstant(name.to_string()),
+            false => Error::UnknownVariable(name.to_string()),
+        })
+    }
+
+    /// Error for the constants
+    pub fn set(&mut self, name: &str, value: impl Into<Value<N>>) -> Result<(), Error> {
+        self.check_assign(name)?;
+        self.variables.insert(name.to_string(), value.into());
+        Ok(())
+    }
+
+    /// Whether `name` can be assigned, constants can't even if `N` has no value for them
+    pub fn check_assign(&self, name: &str) -> Result<(), Error> {
+        match (is_constant(name), self.variables.contains_key(name)) {
+            (true, true) => Err(Error::AssignToConstant(name.to_string())),
+            (true, false) => Err(Error::UnavailableConstant(name.to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Variables and constants in the order of names
+    pub fn variables(&self) -> impl Iterator<Item = (&str, &Value<N>)> {
+        self.variables.iter().map(|(name, x)| (name.as_str(), x))
+    }
+
+    /// Computes RPN `tokens` made by `ParserVisitor::parse`,
+    /// variables are assigned only if there are no errors
+    pub fn execute(&mut self, tokens: Vec<(Token, Span)>) -> Result<Value<N>, SpanError> {
+        let (x, target) = CalcVisitor::with_environment(self).run(tokens)?;
+        if let Some(name) = target {
+            self.set(&name, x.clone())?;
+        }
+        Ok(x)
+    }
+
+    /// Computes RPN `tokens` and gives them to `visitor` in the same pass,
+    /// the variable is assigned only if there are no errors
+    pub fn execute_with<V>(
+        &mut self,
+        tokens: impl IntoIterator<Item = (Token, Span)>,
+        visitor: V,
+    ) -> Result<(Value<N>, V::Output), SpanError>
+    where
+        V: TokenListVisitor,
+        V::Error: Into<SpanError>,
+    {
+        let mut target = None;
+        let calc = CalcVisitor::with_environment(self).inspect(|token| {
+            if let Token::Assign(name) = token {
+                target = Some(name.clone());
+            }
+        });
+        let (x, output) = calc.pair(visitor).run_spanned(tokens)?;
+        if let Some(name) = target {
+            self.set(&name, x.clone())?;
+        }
+        Ok((x, output))
+    }
+}
+
+impl<N: Number> Default for Environment<N> {
+    fn default() -> Self {
+        Environment::new()
+    }
+}
+
+/// `Environment` of a number type picked at runtime, see `Domain::session`
+pub trait Session {
+    /// Computes RPN `tokens`, the result is formatted
+    fn execute(&mut self, tokens: Vec<(Token, Span)>) -> Result<String, SpanError>;
+
+    /// Computes RPN `tokens` and gives them to `visitor` in the same pass
+    fn execute_with(
+        &mut self,
+        tokens: Vec<(Token, Span)>,
+        visitor: &mut dyn DynVisitor,
+    ) -> Result<String, SpanError>;
+
+    /// Names and formatted values of the variables and constants
+    fn variables(&self) -> Vec<(String, String)>;
+}
+
+impl<N: Number> Session for Environment<N> {
+    fn execute(&mut self, tokens: Vec<(Token, Span)>) -> Result<String, SpanError> {
+        Environment::execute(self, tokens).map(|x| x.to_string())
+    }
+
+    fn execute_with(
+        &mut self,
+        tokens: Vec<(Token, Span)>,
+        visitor: &mut dyn DynVisitor,
+    ) -> Result<String, SpanError> {
+        let (x, ()) = Environment::execute_with(self, tokens, Sink(visitor))?;
+        Ok(x.to_string())
+    }
+
+    fn variables(&self) -> Vec<(String, String)> {
+        Environment::variables(self)
+            .map(|(name, x)| (name.to_string(), x.to_string()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_rational::BigRational;
+
+    use super::Environment;
+    use crate::{
+        error::Error,
+        number::Number,
+        span::SpanError,
+        tokenizer::Tokenizer,
+        value::Value,
+        visitors::{ast_visitor::AstVisitor, parser_visitor::ParserVisitor},
+    };
+
+    fn run<N: Number>(environment: &mut Environment<N>, input: &str) -> Result<N, SpanError> {
+        let tokens = Tokenizer::tokenize_spanned(input)?;
+        let x = environment.execute(ParserVisitor::parse(tokens)?)?;
+        Ok(x.into_number("Result")?)
+    }
+
+    #[test]
+    fn scratch_pad() {
+        let mut environment = Environment::<BigRational>::new();
+        environment
+            .set("price", BigRational::from_integer(200.into()))
+            .unwrap();
+        let ratio = |a: i32, b: i32| BigRational::new(a.into(), b.into());
+        assert_eq!(Ok(ratio(7, 100)), run(&mut environment, "rate = 0.07"));
+        assert_eq!(
+            Ok(ratio(214, 1)),
+            run(&mut environment, "total = price * (1 + rate)")
+        );
+        assert_eq!(
+            Some(&Value::Number(ratio(214, 1))),
+            environment.get("total")
+        );
+        let names: Vec<_> = environment.variables().map(|(name, _)| name).collect();
+        assert_eq!(vec!["price", "rate", "total"], names);
+
+        let input = "total = total - discount";
+        let error = run(&mut environment, input).unwrap_err();
+        assert_eq!(
+            "Unknown variable 'discount'\ntotal = total - discount\n                ^^^^^^^^",
+            error.render(input)
+        );
+        assert_eq!(
+            Some(&Value::Number(ratio(214, 1))),
+            environment.get("total")
+        );
+
+        let tokens = Tokenizer::tokenize_spanned("ok = total > price && rate < 0.1").unwrap();
+        let ok = environment.execute(ParserVisitor::parse(tokens).unwrap());
+        assert_eq!(Ok(Value::Bool(true)), ok);
+        assert_eq!(Some(&Value::Bool(true)), environment.get("ok"));
+    }
+
+    #[test]
+    fn execute_with() {
+        let mut environment = Environment::<i32>::new();
+        let rpn = |input: &str| ParserVisitor::parse(Tokenizer::tokenize_spanned(input).unwrap());
+        let (x, tree) = environment
+            .execute_with(rpn("x = (1 + 2) * 3").unwrap(), AstVisitor::new())
+            .unwrap();
+        assert_eq!(
+            (Value::Number(9), "x = (1 + 2) * 3".to_string()),
+            (x, tree.infix())
+        );
+        assert_eq!(Some(&Value::Number(9)), environment.get("x"));
+
+        let error = environment.execute_with(rpn("x = 1 / 0").unwrap(), AstVisitor::new());
+        assert_eq!(Some(Error::DivisionByZero), error.err().map(|e| e.error));
+        assert_eq!(Some(&Value::Number(9)), environment.get("x"));
+    }
+
+    #[test]
+    fn constants() {
+        let mut environment = Environment::<f64>::with_constants();
+        assert_eq!(Ok(std::f64::consts::PI), run(&mut environment, "pi"));
+        assert_eq!(Ok(1.0), run(&mut environment, "ln(e)"));
+        assert_eq!(
+            Some(Error::AssignToConstant("pi".to_string())),
+            run(&mut environment, "pi = 3").err().map(|e| e.error)
+        );
+        assert!(Environment::<i32>::with_constants().get("pi").is_none());
+
+        // the same error for reading and assigning a constant integers don't have
+        let mut environment = Environment::<i32>::with_constants();
+        for (input, name) in [("pi", "pi"), ("pi = 3", "pi"), ("x = 2 * e", "e")] {
+            assert_eq!(
+                Some(Error::UnavailableConstant(name.to_string())),
+                run(&mut environment, input).err().map(|e| e.error),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(
+            Error::UnknownVariable("x".to_string()),
+            environment.lookup("x").unwrap_err()
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt::Display;
+
+use crate::token::Function;
+
+/// Everything that can go wrong from reading the input to computing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Input that can't be read, the text says why
+    Input(String),
+    /// Char that starts no token
+    UnexpectedChar(char),
+    /// Literal that isn't a number, like 1.2.3
+    MalformedNumber(String),
+    /// Exponent too big to compute with, the text says whose
+    TooBig(String),
+    /// ')' without '('
+    UnmatchedParen,
+    /// '(' without ')'
+    UnclosedParen,
+    /// ':' without '?'
+    UnmatchedColon,
+    /// '?' without ':'
+    ExpectedColon,
+    /// Operand missing before the token, `None` at the end
+    MissingOperand(Option<String>),
+    /// Operator missing before the token
+    MissingOperator(String),
+    /// Function name without a call
+    ExpectedParen(Function),
+    /// ',' outside of function arguments
+    UnexpectedComma,
+    /// '=' anywhere but after the variable at the start
+    UnexpectedEquals,
+    /// Call with a wrong number of arguments
+    Arity {
+        function: Function,
+        args: usize,
+    },
+    UnknownFunction(String),
+    UnknownVariable(String),
+    AssignToConstant(String),
+    /// Constant the number type has no value for, like pi of integers
+    UnavailableConstant(String),
+    DivisionByZero,
+    /// Result that doesn't fit the number type, the text is the computation
+    Overflow(String),
+    /// Literal that doesn't fit the number type
+    OutOfRange {
+        literal: String,
+        number: &'static str,
+    },
+    /// Number where a boolean is expected or the other way round,
+    /// the text says which operand
+    TypeMismatch(String),
+    /// Result that the number type has no value for, like sqrt(2) of integers
+    NotInDomain(String),
+    /// Tokens no input makes, like parentheses in RPN
+    InvalidTokens(String),
+    /// Expression without a derivative, the text says which part
+    NotDifferentiable(String),
+}
+
+impl Error {
+    /// Tokens no input makes, with a message saying which
+    pub fn invalid(message: &str) -> Error {
+        Error::InvalidTokens(message.to_string())
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Input(reason) => write!(f, "Unable to read the input: {}", reason),
+            Error::UnexpectedChar(c) => write!(f, "Unexpected token '{}'", c),
+            Error::MalformedNumber(s) => write!(f, "Malformed number '{}'", s),
+            Error::TooBig(s) => write!(f, "{} is too big", s),
+            Error::UnmatchedParen => write!(f, "Unmatched ')'"),
+            Error::UnclosedParen => write!(f, "Unclosed '('"),
+            Error::UnmatchedColon => write!(f, "Unmatched ':'"),
+            Error::ExpectedColon => write!(f, "Expected ':' after '?'"),
+            Error::MissingOperand(Some(token)) => write!(f, "Missing operand before '{}'", token),
+            Error::MissingOperand(None) => write!(f, "Missing operand at the end"),
+            Error::MissingOperator(token) => write!(f, "Missing operator before '{}'", token),
+            Error::ExpectedParen(function) => {
+                write!(f, "Expected '(' after '{}'", function.name())
+            }
+            Error::UnexpectedComma => write!(f, "Unexpected ',' outside of function arguments"),
+            Error::UnexpectedEquals => write!(
+                f,
+                "Only a variable at the start can be assigned, unexpected '='"
+            ),
+            Error::Arity { function, args } => {
+                let plural = |n: usize| if n == 1 { "argument" } else { "arguments" };
+                write!(f, "Function '{}' takes ", function.name())?;
+                match function.arity() {
+                    (min, None) => write!(f, "at least {} {}", min, plural(min))?,
+                    (min, Some(max)) if min == max => write!(f, "{} {}", min, plural(min))?,
+                    (min, Some(max)) => write!(f, "{} to {} arguments", min, max)?,
+                }
+                write!(f, ", got {}", args)
+            }
+            Error::UnknownFunction(name) => write!(f, "Unknown function '{}'", name),
+            Error::UnknownVariable(name) => write!(f, "Unknown variable '{}'", name),
+            Error::AssignToConstant(name) => write!(f, "Cannot assign to constant '{}'", name),
+            Error::UnavailableConstant(name) => {
+                write!(f, "Constant '{}' isn't available in this domain", name)
+            }
+            Error::DivisionByZero => write!(f, "Division by zero"),
+            Error::Overflow(computation) => write!(f, "Overflow in {}", computation),
+            Error::OutOfRange { literal, number } => {
+                write!(f, "Literal {} is out of range of {}", literal, number)
+            }
+            Error::NotInDomain(message)
+            | Error::InvalidTokens(message)
+            | Error::TypeMismatch(message) => write!(f, "{}", message),
+            Error::NotDifferentiable(part) => write!(f, "Cannot differentiate {}", part),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+    use crate::{
+        span::SpanError,
+        token::{Function, TokenListVisitor},
+        tokenizer::Tokenizer,
+        value::Value,
+        visitors::{calc_visitor::CalcVisitor, parser_visitor::ParserVisitor},
+    };
+
+    fn error(input: &str) -> Error {
+        let calc = || -> Result<Value<i32>, SpanError> {
+            let rpn = ParserVisitor::parse(Tokenizer::tokenize_spanned(input)?)?;
+            CalcVisitor::visit_spanned(rpn)
+        };
+        calc().unwrap_err().error
+    }
+
+    #[test]
+    fn kinds() {
+        assert_eq!(Error::UnexpectedChar('$'), error("2 $ 3"));
+        assert_eq!(Error::UnclosedParen, error("(1 + 2"));
+        assert_eq!(Error::MissingOperand(Some("*".to_string())), error("* 2"));
+        assert_eq!(
+            Error::Arity {
+                function: Function::Log,
+                args: 1
+            },
+            error("log(2)")
+        );
+        assert_eq!(Error::DivisionByZero, error("1 / (2 - 2)"));
+        assert_eq!(Error::UnknownVariable("x".to_string()), error("x + 1"));
+        assert!(matches!(error("2147483647 + 1"), Error::Overflow(_)));
+        assert_eq!(Error::ExpectedColon, error("1 < 2 ? 3"));
+        assert_eq!(Error::UnmatchedColon, error("1 : 2"));
+        assert!(matches!(error("!1"), Error::TypeMismatch(_)));
+    }
+
+    #[test]
+    fn source() {
+        let error: Box<dyn std::error::Error> = Box::new(SpanError::from(Error::DivisionByZero));
+        assert_eq!(
+            Some("Division by zero".to_string()),
+            error.source().map(|e| e.to_string())
+        );
+    }
+}
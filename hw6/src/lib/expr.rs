@@ -0,0 +1,139 @@
+use std::fmt::Display;
+
+use crate::token::{Function, Literal, Operator};
+
+/// Expression tree, built of RPN by `AstVisitor`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Number(Literal),
+    Variable(String),
+    Unary(Operator, Box<Expr>),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+    /// `condition ? a : b`
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+    /// `name = value`, only at the top
+    Assign(String, Box<Expr>),
+}
+
+impl Expr {
+    /// How tightly the expression holds together, atoms are never split
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Unary(op, _) | Expr::Binary(op, _, _) => op.precedence(),
+            Expr::Conditional(_, _, _) => Operator::Conditional.precedence(),
+            Expr::Assign(_, _) => 0,
+            Expr::Number(_) | Expr::Variable(_) | Expr::Call(_, _) => u8::MAX,
+        }
+    }
+
+    /// Infix notation with only the parentheses the parser needs
+    pub fn infix(&self) -> String {
+        match self {
+            Expr::Number(x) => x.to_string(),
+            Expr::Variable(name) => name.clone(),
+            // the operand of a unary operator extends over '^' only
+            Expr::Unary(op, x) => format!("{}{}", op, x.operand(op.precedence(), false)),
+            Expr::Binary(op, x1, x2) => {
+                let p = op.precedence();
+                let left = x1.operand(p, !op.left_associative());
+                // a unary operator on the right can't take anything of its left
+                let right = match **x2 {
+                    Expr::Unary(_, _) => x2.infix(),
+                    _ => x2.operand(p, op.left_associative()),
+                };
+                format!("{} {} {}", left, op, right)
+            }
+            Expr::Call(function, args) => {
+                let args: Vec<_> = args.iter().map(Expr::infix).collect();
+                format!("{}({})", function.name(), args.join(", "))
+            }
+            // the condition and the first branch of a conditional are parenthesized
+            // if they are conditionals too, the second branch isn't
+            Expr::Conditional(c, x1, x2) => {
+                let p = Operator::Conditional.precedence();
+                format!(
+                    "{} ? {} : {}",
+                    c.operand(p, true),
+                    x1.operand(p, true),
+                    x2.operand(p, false)
+                )
+            }
+            Expr::Assign(name, x) => format!("{} = {}", name, x.infix()),
+        }
+    }
+
+    /// Infix of an operand of an operator with precedence `p`, parenthesized
+    /// if it binds weaker, or the same with `strict`
+    fn operand(&self, p: u8, strict: bool) -> String {
+        let q = self.precedence();
+        if q < p || (q == p && strict) {
+            format!("({})", self.infix())
+        } else {
+            self.infix()
+        }
+    }
+
+    /// Polish notation, spelled like the RPN: neg, max:3, `= name value`
+    pub fn prefix(&self) -> String {
+        let mut s = self.label();
+        for x in self.children() {
+            s.push(' ');
+            s.push_str(&x.prefix());
+        }
+        s
+    }
+
+    /// One node per line with its children indented under it, spelled like `prefix`
+    pub fn tree(&self) -> String {
+        let mut s = String::new();
+        self.write_tree(0, &mut s);
+        s
+    }
+
+    fn write_tree(&self, depth: usize, s: &mut String) {
+        if depth > 0 {
+            s.push('\n');
+        }
+        s.push_str(&"  ".repeat(depth));
+        s.push_str(&self.label());
+        for x in self.children() {
+            x.write_tree(depth + 1, s);
+        }
+    }
+
+    /// Node without its children
+    fn label(&self) -> String {
+        match self {
+            Expr::Number(x) => x.to_string(),
+            Expr::Variable(name) => name.clone(),
+            Expr::Unary(Operator::UnaryMinus, _) => "neg".to_string(),
+            Expr::Unary(Operator::UnaryPlus, _) => "pos".to_string(),
+            Expr::Unary(op, _) => op.to_string(),
+            Expr::Binary(op, _, _) => op.to_string(),
+            Expr::Call(function, args) => match function.arity() {
+                (_, None) => format!("{}:{}", function.name(), args.len()),
+                _ => function.name().to_string(),
+            },
+            Expr::Conditional(_, _, _) => Operator::Conditional.to_string(),
+            Expr::Assign(name, _) => format!("= {}", name),
+        }
+    }
+
+    fn children(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Number(_) | Expr::Variable(_) => vec![],
+            Expr::Unary(_, x) | Expr::Assign(_, x) => vec![x],
+            Expr::Binary(_, x1, x2) => vec![x1, x2],
+            Expr::Call(_, args) => args.iter().collect(),
+            Expr::Conditional(c, x1, x2) => vec![c, x1, x2],
+        }
+    }
+}
+
+/// Infix notation
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.infix())
+    }
+}
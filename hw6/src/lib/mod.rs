@@ -1,3 +1,11 @@
+pub mod bytecode;
+pub mod environment;
+pub mod error;
+pub mod expr;
+pub mod number;
+pub mod span;
+pub mod symbolic;
 pub mod token;
 pub mod tokenizer;
+pub mod value;
 pub mod visitors;
@@ -0,0 +1,833 @@
+use std::{
+    fmt::Display,
+    num::{Saturating, Wrapping},
+    str::FromStr,
+};
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, One, Pow, Signed, ToPrimitive, Zero};
+
+use crate::{
+    environment::{Environment, Session},
+    error::Error,
+    token::{Function, Literal, Token, TokenListVisitor},
+    visitors::calc_visitor::CalcVisitor,
+};
+
+/// Values `CalcVisitor` computes with, ordered for the comparisons
+pub trait Number: Sized + Clone + Display + PartialOrd {
+    fn from_literal(literal: &Literal) -> Result<Self, Error>;
+    fn add(self, rhs: Self) -> Result<Self, Error>;
+    fn sub(self, rhs: Self) -> Result<Self, Error>;
+    fn mul(self, rhs: Self) -> Result<Self, Error>;
+    fn div(self, rhs: Self) -> Result<Self, Error>;
+    fn rem(self, rhs: Self) -> Result<Self, Error>;
+    fn pow(self, rhs: Self) -> Result<Self, Error>;
+    fn neg(self) -> Result<Self, Error>;
+    /// Built-in function, `args` match its arity
+    fn call(function: Function, args: Vec<Self>) -> Result<Self, Error>;
+}
+
+/// Literals with longer exponents would take too much memory as exact numbers
+const MAX_EXACT_EXPONENT: u64 = 10_000;
+
+fn to_ratio(literal: &Literal) -> Result<BigRational, Error> {
+    if literal.exponent().unsigned_abs() > MAX_EXACT_EXPONENT {
+        return Err(Error::TooBig(format!("Exponent of {}", literal)));
+    }
+    let mut digits = literal
+        .digits()
+        .parse::<BigInt>()
+        .expect("literal digits are digits");
+    if literal.is_negative() {
+        digits = -digits;
+    }
+    let power = Pow::pow(BigInt::from(10), literal.exponent().unsigned_abs());
+    Ok(if literal.exponent() >= 0 {
+        BigRational::from_integer(digits * power)
+    } else {
+        BigRational::new(digits, power)
+    })
+}
+
+fn to_integer(literal: &Literal) -> Result<BigInt, Error> {
+    let ratio = to_ratio(literal)?;
+    if !ratio.is_integer() {
+        return Err(Error::NotInDomain(format!("{} is not an integer", literal)));
+    }
+    Ok(ratio.to_integer())
+}
+
+/// Bigger powers of exact numbers would take too much memory
+const MAX_EXACT_POWER: u32 = 100_000;
+
+/// Exponent of integer `pow`, integers have no negative powers
+fn integer_exponent(exponent: &BigInt) -> Result<u32, Error> {
+    if exponent.is_negative() {
+        return Err(negative_exponent(exponent));
+    }
+    exponent
+        .to_u32()
+        .filter(|e| *e <= MAX_EXACT_POWER)
+        .ok_or_else(|| Error::TooBig(format!("Exponent {}", exponent)))
+}
+
+fn negative_exponent(exponent: impl Display) -> Error {
+    Error::NotInDomain(format!("Negative exponent {} of an integer", exponent))
+}
+
+fn overflow(a: impl Display, op: &str, b: impl Display) -> Error {
+    Error::Overflow(format!("{} {} {}", a, op, b))
+}
+
+/// Debug keeps big and small floats short, like 1e308
+fn float_overflow(a: f64, op: &str, b: f64) -> Error {
+    Error::Overflow(format!("{:?} {} {:?}", a, op, b))
+}
+
+fn out_of_range(literal: &Literal, number: &'static str) -> Error {
+    Error::OutOfRange {
+        literal: literal.to_string(),
+        number,
+    }
+}
+
+/// Call as it was written, like max(1, 2)
+fn call_text<N: Display>(function: Function, args: &[N]) -> String {
+    let args = args.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+    format!("{}({})", function.name(), args.join(", "))
+}
+
+fn not_exact<N: Display>(function: Function, args: &[N]) -> Error {
+    Error::NotInDomain(format!(
+        "{} is not a rational number, use the float domain",
+        call_text(function, args)
+    ))
+}
+
+/// Square root of a perfect square
+fn exact_sqrt(x: &BigInt) -> Option<BigInt> {
+    let root = x.sqrt();
+    (&root * &root == *x).then_some(root)
+}
+
+/// Functions of exact numbers, results that aren't rational are errors
+fn exact_call(function: Function, args: Vec<BigRational>) -> Result<BigRational, Error> {
+    // checked before not_exact, the float domain can't help with these
+    let not_real = || {
+        Error::NotInDomain(format!(
+            "{} is not a real number",
+            call_text(function, &args)
+        ))
+    };
+    let x = args[0].clone();
+    match function {
+        Function::Max => Ok(args.into_iter().max().expect("max has arguments")),
+        Function::Min => Ok(args.into_iter().min().expect("min has arguments")),
+        Function::Abs => Ok(x.abs()),
+        Function::Sqrt if x.is_negative() => Err(not_real()),
+        Function::Sqrt => match (exact_sqrt(x.numer()), exact_sqrt(x.denom())) {
+            (Some(numer), Some(denom)) => Ok(BigRational::new(numer, denom)),
+            _ => Err(not_exact(function, &args)),
+        },
+        Function::Sin if x.is_zero() => Ok(BigRational::zero()),
+        Function::Cos if x.is_zero() => Ok(BigRational::one()),
+        Function::Ln if !x.is_positive() => Err(not_real()),
+        Function::Ln if x.is_one() => Ok(BigRational::zero()),
+        Function::Sin | Function::Cos | Function::Ln => Err(not_exact(function, &args)),
+        Function::Log if !x.is_positive() || x.is_one() || !args[1].is_positive() => {
+            Err(not_real())
+        }
+        Function::Log => {
+            let (base, x) = (&args[0], &args[1]);
+            let float = |x: &BigRational| x.to_f64().unwrap_or(f64::NAN);
+            let estimate = f64::call(function, vec![float(base), float(x)])?;
+            // the estimate is close to the exponent if it's an integer
+            let exponent = BigRational::from_integer(
+                BigInt::from_f64(estimate.round()).ok_or_else(|| not_exact(function, &args))?,
+            );
+            match Number::pow(base.clone(), exponent.clone()) {
+                Ok(power) if power == *x => Ok(exponent),
+                _ => Err(not_exact(function, &args)),
+            }
+        }
+        Function::Round => {
+            let digits = match args.get(1) {
+                Some(digits) if !digits.is_integer() => {
+                    return Err(Error::NotInDomain(format!(
+                        "Digits {} of round are not an integer",
+                        digits
+                    )))
+                }
+                Some(digits) => digits.to_integer(),
+                None => BigInt::zero(),
+            };
+            let scale = BigRational::from_integer(Pow::pow(
+                BigInt::from(10),
+                integer_exponent(&digits.abs())?,
+            ));
+            // round() goes half away from zero
+            Ok(if digits.is_negative() {
+                (x / &scale).round() * scale
+            } else {
+                (x * &scale).round() / scale
+            })
+        }
+    }
+}
+
+/// Functions of integers, computed exactly, results must be integers
+fn integer_call(function: Function, args: Vec<BigInt>) -> Result<BigInt, Error> {
+    let ratios = args
+        .iter()
+        .cloned()
+        .map(BigRational::from_integer)
+        .collect();
+    let x = exact_call(function, ratios)?;
+    if !x.is_integer() {
+        return Err(Error::NotInDomain(format!(
+            "{} is not an integer",
+            call_text(function, &args)
+        )));
+    }
+    Ok(x.to_integer())
+}
+
+fn wrap(x: BigInt) -> Wrapping<i32> {
+    let low_bits = x & BigInt::from(u32::MAX);
+    Wrapping(low_bits.to_u32().expect("masked to 32 bits") as i32)
+}
+
+fn saturate(x: BigInt) -> Saturating<i32> {
+    Saturating(
+        x.to_i32()
+            .unwrap_or(if x.is_negative() { i32::MIN } else { i32::MAX }),
+    )
+}
+
+/// Machine integers, overflow is an error and division truncates
+impl Number for i32 {
+    fn from_literal(literal: &Literal) -> Result<Self, Error> {
+        to_integer(literal)?
+            .to_i32()
+            .ok_or_else(|| out_of_range(literal, "i32"))
+    }
+
+    fn add(self, rhs: Self) -> Result<Self, Error> {
+        self.checked_add(rhs)
+            .ok_or_else(|| overflow(self, "+", rhs))
+    }
+
+    fn sub(self, rhs: Self) -> Result<Self, Error> {
+        self.checked_sub(rhs)
+            .ok_or_else(|| overflow(self, "-", rhs))
+    }
+
+    fn mul(self, rhs: Self) -> Result<Self, Error> {
+        self.checked_mul(rhs)
+            .ok_or_else(|| overflow(self, "*", rhs))
+    }
+
+    fn div(self, rhs: Self) -> Result<Self, Error> {
+        if rhs == 0 {
+            return Err(Error::DivisionByZero);
+        }
+        self.checked_div(rhs)
+            .ok_or_else(|| overflow(self, "/", rhs))
+    }
+
+    fn rem(self, rhs: Self) -> Result<Self, Error> {
+        if rhs == 0 {
+            return Err(Error::DivisionByZero);
+        }
+        self.checked_rem(rhs)
+            .ok_or_else(|| overflow(self, "%", rhs))
+    }
+
+    fn pow(self, rhs: Self) -> Result<Self, Error> {
+        let exponent = u32::try_from(rhs).map_err(|_| negative_exponent(rhs))?;
+        self.checked_pow(exponent)
+            .ok_or_else(|| overflow(self, "^", rhs))
+    }
+
+    fn neg(self) -> Result<Self, Error> {
+        self.checked_neg()
+            .ok_or_else(|| Error::Overflow(format!("-({})", self)))
+    }
+
+    fn call(function: Function, args: Vec<Self>) -> Result<Self, Error> {
+        integer_call(function, args.iter().map(|x| BigInt::from(*x)).collect())?
+            .to_i32()
+            .ok_or_else(|| Error::Overflow(call_text(function, &args)))
+    }
+}
+
+/// Machine integers that wrap around like two's complement, literals too
+impl Number for Wrapping<i32> {
+    fn from_literal(literal: &Literal) -> Result<Self, Error> {
+        Ok(wrap(to_integer(literal)?))
+    }
+
+    fn add(self, rhs: Self) -> Result<Self, Error> {
+        Ok(self + rhs)
+    }
+
+    fn sub(self, rhs: Self) -> Result<Self, Error> {
+        Ok(self - rhs)
+    }
+
+    fn mul(self, rhs: Self) -> Result<Self, Error> {
+        Ok(self * rhs)
+    }
+
+    fn div(self, rhs: Self) -> Result<Self, Error> {
+        if rhs.0 == 0 {
+            return Err(Error::DivisionByZero);
+        }
+        Ok(self / rhs)
+    }
+
+    fn rem(self, rhs: Self) -> Result<Self, Error> {
+        if rhs.0 == 0 {
+            return Err(Error::DivisionByZero);
+        }
+        Ok(self % rhs)
+    }
+
+    fn pow(self, rhs: Self) -> Result<Self, Error> {
+        let exponent = u32::try_from(rhs.0).map_err(|_| negative_exponent(rhs))?;
+        Ok(Wrapping(self.0.wrapping_pow(exponent)))
+    }
+
+    fn neg(self) -> Result<Self, Error> {
+        Ok(-self)
+    }
+
+    fn call(function: Function, args: Vec<Self>) -> Result<Self, Error> {
+        let args = args.iter().map(|x| BigInt::from(x.0)).collect();
+        Ok(wrap(integer_call(function, args)?))
+    }
+}
+
+/// Machine integers clamped to the bounds of i32, literals too
+impl Number for Saturating<i32> {
+    fn from_literal(literal: &Literal) -> Result<Self, Error> {
+        Ok(saturate(to_integer(literal)?))
+    }
+
+    fn add(self, rhs: Self) -> Result<Self, Error> {
+        Ok(self + rhs)
+    }
+
+    fn sub(self, rhs: Self) -> Result<Self, Error> {
+        Ok(self - rhs)
+    }
+
+    fn mul(self, rhs: Self) -> Result<Self, Error> {
+        Ok(self * rhs)
+    }
+
+    fn div(self, rhs: Self) -> Result<Self, Error> {
+        if rhs.0 == 0 {
+            return Err(Error::DivisionByZero);
+        }
+        Ok(Saturating(self.0.saturating_div(rhs.0)))
+    }
+
+    fn rem(self, rhs: Self) -> Result<Self, Error> {
+        if rhs.0 == 0 {
+            return Err(Error::DivisionByZero);
+        }
+        // only i32::MIN % -1 overflows, and its result is 0 anyway
+        Ok(Saturating(self.0.checked_rem(rhs.0).unwrap_or(0)))
+    }
+
+    fn pow(self, rhs: Self) -> Result<Self, Error> {
+        let exponent = u32::try_from(rhs.0).map_err(|_| negative_exponent(rhs))?;
+        Ok(Saturating(self.0.saturating_pow(exponent)))
+    }
+
+    fn neg(self) -> Result<Self, Error> {
+        Ok(-self)
+    }
+
+    fn call(function: Function, args: Vec<Self>) -> Result<Self, Error> {
+        let args = args.iter().map(|x| BigInt::from(x.0)).collect();
+        Ok(saturate(integer_call(function, args)?))
+    }
+}
+
+/// Overflow to infinity is an error
+impl Number for f64 {
+    fn from_literal(literal: &Literal) -> Result<Self, Error> {
+        let sign = if literal.is_negative() { "-" } else { "" };
+        let x = format!("{}{}e{}", sign, literal.digits(), literal.exponent())
+            .parse::<f64>()
+            .map_err(|_| Error::MalformedNumber(literal.to_string()))?;
+        if x.is_infinite() {
+            return Err(out_of_range(literal, "f64"));
+        }
+        Ok(x)
+    }
+
+    fn add(self, rhs: Self) -> Result<Self, Error> {
+        finite(self + rhs, || float_overflow(self, "+", rhs))
+    }
+
+    fn sub(self, rhs: Self) -> Result<Self, Error> {
+        finite(self - rhs, || float_overflow(self, "-", rhs))
+    }
+
+    fn mul(self, rhs: Self) -> Result<Self, Error> {
+        finite(self * rhs, || float_overflow(self, "*", rhs))
+    }
+
+    fn div(self, rhs: Self) -> Result<Self, Error> {
+        if rhs == 0.0 {
+            return Err(Error::DivisionByZero);
+        }
+        finite(self / rhs, || float_overflow(self, "/", rhs))
+    }
+
+    fn rem(self, rhs: Self) -> Result<Self, Error> {
+        if rhs == 0.0 {
+            return Err(Error::DivisionByZero);
+        }
+        Ok(self % rhs)
+    }
+
+    fn pow(self, rhs: Self) -> Result<Self, Error> {
+        let x = self.powf(rhs);
+        if x.is_nan() {
+            return Err(Error::NotInDomain(format!(
+                "{:?} ^ {:?} is not a real number",
+                self, rhs
+            )));
+        }
+        finite(x, || float_overflow(self, "^", rhs))
+    }
+
+    fn neg(self) -> Result<Self, Error> {
+        Ok(-self)
+    }
+
+    /// Angles are in radians
+    fn call(function: Function, args: Vec<Self>) -> Result<Self, Error> {
+        let not_real = || {
+            Error::NotInDomain(format!(
+                "{} is not a real number",
+                call_text(function, &args)
+            ))
+        };
+        let x = args[0];
+        let res = match function {
+            Function::Max => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Function::Min => args.iter().copied().fold(f64::INFINITY, f64::min),
+            Function::Abs => x.abs(),
+            Function::Sqrt if x < 0.0 => return Err(not_real()),
+            Function::Sqrt => x.sqrt(),
+            Function::Sin => x.sin(),
+            Function::Cos => x.cos(),
+            Function::Ln if x <= 0.0 => return Err(not_real()),
+            Function::Ln => x.ln(),
+            Function::Log if x <= 0.0 || x == 1.0 || args[1] <= 0.0 => return Err(not_real()),
+            Function::Log => args[1].ln() / x.ln(),
+            Function::Round => match args.get(1) {
+                Some(digits) if digits.fract() != 0.0 => {
+                    return Err(Error::NotInDomain(format!(
+                        "Digits {:?} of round are not an integer",
+                        digits
+                    )))
+                }
+                Some(digits) => {
+                    let scale = 10f64.powf(*digits);
+                    (x * scale).round() / scale
+                }
+                None => x.round(),
+            },
+        };
+        finite(res, || Error::Overflow(call_text(function, &args)))
+    }
+}
+
+fn finite(x: f64, error: impl FnOnce() -> Error) -> Result<f64, Error> {
+    if x.is_finite() {
+        Ok(x)
+    } else {
+        Err(error())
+    }
+}
+
+/// Arbitrary-precision integers, division truncates
+impl Number for BigInt {
+    fn from_literal(literal: &Literal) -> Result<Self, Error> {
+        to_integer(literal)
+    }
+
+    fn add(self, rhs: Self) -> Result<Self, Error> {
+        Ok(self + rhs)
+    }
+
+    fn sub(self, rhs: Self) -> Result<Self, Error> {
+        Ok(self - rhs)
+    }
+
+    fn mul(self, rhs: Self) -> Result<Self, Error> {
+        Ok(self * rhs)
+    }
+
+    fn div(self, rhs: Self) -> Result<Self, Error> {
+        if rhs.is_zero() {
+            return Err(Error::DivisionByZero);
+        }
+        Ok(self / rhs)
+    }
+
+    fn rem(self, rhs: Self) -> Result<Self, Error> {
+        if rhs.is_zero() {
+            return Err(Error::DivisionByZero);
+        }
+        Ok(self % rhs)
+    }
+
+    fn pow(self, rhs: Self) -> Result<Self, Error> {
+        Ok(Pow::pow(self, integer_exponent(&rhs)?))
+    }
+
+    fn neg(self) -> Result<Self, Error> {
+        Ok(-self)
+    }
+
+    fn call(function: Function, args: Vec<Self>) -> Result<Self, Error> {
+        integer_call(function, args)
+    }
+}
+
+/// Exact fractions of arbitrary-precision integers
+impl Number for BigRational {
+    fn from_literal(literal: &Literal) -> Result<Self, Error> {
+        to_ratio(literal)
+    }
+
+    fn add(self, rhs: Self) -> Result<Self, Error> {
+        Ok(self + rhs)
+    }
+
+    fn sub(self, rhs: Self) -> Result<Self, Error> {
+        Ok(self - rhs)
+    }
+
+    fn mul(self, rhs: Self) -> Result<Self, Error> {
+        Ok(self * rhs)
+    }
+
+    fn div(self, rhs: Self) -> Result<Self, Error> {
+        if rhs.is_zero() {
+            return Err(Error::DivisionByZero);
+        }
+        Ok(self / rhs)
+    }
+
+    fn rem(self, rhs: Self) -> Result<Self, Error> {
+        if rhs.is_zero() {
+            return Err(Error::DivisionByZero);
+        }
+        Ok(self % rhs)
+    }
+
+    /// Only integer exponents keep the result rational
+    fn pow(self, rhs: Self) -> Result<Self, Error> {
+        if !rhs.is_integer() {
+            return Err(Error::NotInDomain(format!(
+                "Exponent {} of a rational is not an integer",
+                rhs
+            )));
+        }
+        let exponent = rhs.to_integer();
+        let power = integer_exponent(&exponent.abs())?;
+        let x = Pow::pow(self, power);
+        if exponent.is_negative() {
+            if x.is_zero() {
+                return Err(Error::DivisionByZero);
+            }
+            Ok(x.recip())
+        } else {
+            Ok(x)
+        }
+    }
+
+    fn neg(self) -> Result<Self, Error> {
+        Ok(-self)
+    }
+
+    fn call(function: Function, args: Vec<Self>) -> Result<Self, Error> {
+        exact_call(function, args)
+    }
+}
+
+/// What `Domain::Int` does when a result or a literal doesn't fit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    /// Error, `i32`
+    #[default]
+    Checked,
+    /// Clamp to the bounds, `Saturating<i32>`
+    Saturating,
+    /// Wrap around, `Wrapping<i32>`
+    Wrapping,
+}
+
+impl FromStr for Overflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "checked" => Ok(Overflow::Checked),
+            "saturating" => Ok(Overflow::Saturating),
+            "wrapping" => Ok(Overflow::Wrapping),
+            _ => Err(format!(
+                "Unknown overflow mode '{}', expected one of: checked, saturating, wrapping",
+                s
+            )),
+        }
+    }
+}
+
+/// Number type picked at runtime, e.g. by a command line flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Domain {
+    /// `i32` or its saturating or wrapping variant
+    Int(Overflow),
+    /// `f64`
+    Float,
+    /// `BigRational`
+    #[default]
+    Rational,
+    /// `BigInt`
+    BigInt,
+}
+
+impl Domain {
+    pub const NAMES: [&'static str; 4] = ["int", "float", "rational", "bigint"];
+
+    /// Computes RPN `tokens` with `CalcVisitor` of this domain
+    pub fn evaluate(self, tokens: Vec<Token>) -> Result<String, Error> {
+        fn eval<N: Number>(tokens: Vec<Token>) -> Result<String, Error> {
+            CalcVisitor::<N>::visit_tokens(tokens)
+                .map(|x| x.to_string())
+                .map_err(|e| e.error)
+        }
+        match self {
+            Domain::Int(Overflow::Checked) => eval::<i32>(tokens),
+            Domain::Int(Overflow::Saturating) => eval::<Saturating<i32>>(tokens),
+            Domain::Int(Overflow::Wrapping) => eval::<Wrapping<i32>>(tokens),
+            Domain::Float => eval::<f64>(tokens),
+            Domain::Rational => eval::<BigRational>(tokens),
+            Domain::BigInt => eval::<BigInt>(tokens),
+        }
+    }
+
+    /// Environment with the constants for statements of one session
+    pub fn session(self) -> Box<dyn Session> {
+        match self {
+            Domain::Int(Overflow::Checked) => Box::new(Environment::<i32>::with_constants()),
+            Domain::Int(Overflow::Saturating) => {
+                Box::new(Environment::<Saturating<i32>>::with_constants())
+            }
+            Domain::Int(Overflow::Wrapping) => {
+                Box::new(Environment::<Wrapping<i32>>::with_constants())
+            }
+            Domain::Float => Box::new(Environment::<f64>::with_constants()),
+            Domain::Rational => Box::new(Environment::<BigRational>::with_constants()),
+            Domain::BigInt => Box::new(Environment::<BigInt>::with_constants()),
+        }
+    }
+}
+
+impl FromStr for Domain {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "int" => Ok(Domain::Int(Overflow::Checked)),
+            "float" => Ok(Domain::Float),
+            "rational" => Ok(Domain::Rational),
+            "bigint" => Ok(Domain::BigInt),
+            _ => Err(format!(
+                "Unknown number type '{}', expected one of: {}",
+                s,
+                Domain::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigInt;
+    use num_rational::BigRational;
+    use num_traits::Pow;
+
+    use super::{Domain, Number};
+    use crate::{
+        error::Error,
+        token::{Function, Literal},
+        tokenizer::Tokenizer,
+        visitors::parser_visitor::ParserVisitor,
+    };
+
+    fn literal(s: &str) -> Literal {
+        Literal::parse(s).unwrap()
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(1500, i32::from_literal(&literal("1.5e3")).unwrap());
+        assert!(i32::from_literal(&literal("1.5")).is_err());
+        assert!(i32::from_literal(&literal("3e9")).is_err());
+        assert_eq!(0.0025, f64::from_literal(&literal("2.5e-3")).unwrap());
+        assert_eq!(
+            Pow::pow(BigInt::from(10), 30u32),
+            BigInt::from_literal(&literal("1e30")).unwrap()
+        );
+        assert_eq!(
+            BigRational::new(3.into(), 2.into()),
+            BigRational::from_literal(&literal("1.5")).unwrap()
+        );
+        assert!(BigRational::from_literal(&literal("1e100000")).is_err());
+        for edge in ["1e9223372036854775807", "1e-9223372036854775808"] {
+            assert!(matches!(
+                i32::from_literal(&literal(edge)),
+                Err(Error::TooBig(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn domains() {
+        let eval = |domain: &str, rpn: &str| {
+            let tokens = Tokenizer::tokenize(rpn.to_string()).unwrap();
+            domain.parse::<Domain>().unwrap().evaluate(tokens)
+        };
+        assert_eq!("3", eval("int", "7 2 /").unwrap());
+        assert_eq!("3.5", eval("float", "7 2 /").unwrap());
+        assert_eq!("7/2", eval("rational", "7 2 /").unwrap());
+        assert_eq!("0", eval("rational", "0.1 0.2 + 0.3 -").unwrap());
+        assert_ne!("0", eval("float", "0.1 0.2 + 0.3 -").unwrap());
+        assert_eq!(
+            "100000000000000000000",
+            eval("bigint", "1e10 1e10 *").unwrap()
+        );
+        assert!(eval("int", "1e10 1e10 *").is_err());
+        assert!(eval("rational", "1 0 /").is_err());
+        assert!("complex".parse::<Domain>().is_err());
+    }
+
+    #[test]
+    fn overflow() {
+        let eval = |overflow: &str, rpn: &str| {
+            let tokens = Tokenizer::tokenize(rpn.to_string()).unwrap();
+            Domain::Int(overflow.parse().unwrap()).evaluate(tokens)
+        };
+        assert_eq!(
+            Err(Error::Overflow("2147483647 + 1".to_string())),
+            eval("checked", "2147483647 1 +")
+        );
+        assert_eq!(
+            Err(Error::Overflow("-2147483647 - 2".to_string())),
+            eval("checked", "0 2147483647 - 2 -")
+        );
+        assert_eq!(
+            Err(Error::OutOfRange {
+                literal: "3000000000".to_string(),
+                number: "i32"
+            }),
+            eval("checked", "3000000000")
+        );
+        assert_eq!("-2147483648", eval("wrapping", "2147483647 1 +").unwrap());
+        assert_eq!("-1294967296", eval("wrapping", "3000000000").unwrap());
+        assert_eq!("2147483647", eval("saturating", "2147483647 1 +").unwrap());
+        assert_eq!("2147483647", eval("saturating", "3000000000 1 *").unwrap());
+        assert_eq!(Err(Error::DivisionByZero), eval("wrapping", "1 0 /"));
+    }
+
+    #[test]
+    fn min_literal() {
+        let eval = |overflow: &str, input: &str| {
+            let rpn = ParserVisitor::parse(Tokenizer::tokenize_spanned(input).unwrap()).unwrap();
+            let mut session = Domain::Int(overflow.parse().unwrap()).session();
+            session.execute(rpn).map_err(|e| e.error)
+        };
+        for overflow in ["checked", "wrapping", "saturating"] {
+            assert_eq!(Ok("-2147483648".to_string()), eval(overflow, "-2147483648"));
+            assert_eq!(
+                Ok("-2147483647".to_string()),
+                eval(overflow, "-2147483648 + 1")
+            );
+        }
+        assert_eq!(
+            Err(Error::OutOfRange {
+                literal: "-2147483649".to_string(),
+                number: "i32"
+            }),
+            eval("checked", "-2147483649")
+        );
+        assert_eq!(
+            Ok("2147483647".to_string()),
+            eval("wrapping", "-2147483649")
+        );
+        assert_eq!(
+            Ok("-2147483648".to_string()),
+            eval("saturating", "-3000000000")
+        );
+        // '-' of a power isn't a part of the literal
+        assert!(eval("checked", "-2147483648 ^ 1").is_err());
+    }
+
+    #[test]
+    fn exact_functions() {
+        let ratio = |x: i64| BigRational::from_integer(x.into());
+        let call = |name: &str, args: &[i64]| {
+            let function = Function::from_name(name).unwrap();
+            BigRational::call(function, args.iter().copied().map(ratio).collect())
+        };
+        assert_eq!(Ok(ratio(0)), call("ln", &[1]));
+        assert_eq!(Ok(ratio(3)), call("log", &[2, 8]));
+        assert_eq!(
+            Err(Error::NotInDomain(
+                "ln(2) is not a rational number, use the float domain".to_string()
+            )),
+            call("ln", &[2])
+        );
+        // not a float either, so no hint to use the float domain
+        for (name, args, text) in [
+            ("ln", &[0][..], "ln(0)"),
+            ("ln", &[-2], "ln(-2)"),
+            ("log", &[2, 0], "log(2, 0)"),
+            ("log", &[-2, 4], "log(-2, 4)"),
+            ("log", &[1, 1], "log(1, 1)"),
+            ("sqrt", &[-4], "sqrt(-4)"),
+        ] {
+            assert_eq!(
+                Err(Error::NotInDomain(format!("{} is not a real number", text))),
+                call(name, args)
+            );
+        }
+    }
+
+    #[test]
+    fn float_overflow() {
+        assert_eq!(
+            Err(Error::Overflow("1e308 * 10.0".to_string())),
+            f64::mul(1e308, 10.0)
+        );
+        assert_eq!(
+            Err(Error::OutOfRange {
+                literal: "1e400".to_string(),
+                number: "f64"
+            }),
+            f64::from_literal(&literal("1e400"))
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use std::fmt::Display;
+
+use crate::error::Error;
+
+/// Bytes `start..end` of the input line a token came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// Empty span right after this one
+    pub fn after(self) -> Span {
+        Span::new(self.end, self.end)
+    }
+
+    /// From the start of this span to the end of `other`
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start, other.end)
+    }
+}
+
+/// Error about a part of the input, if it's known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpanError {
+    pub error: Error,
+    pub span: Option<Span>,
+}
+
+impl SpanError {
+    pub fn new(error: Error, span: Span) -> SpanError {
+        SpanError {
+            error,
+            span: Some(span),
+        }
+    }
+
+    /// Puts the error at `span` unless it already has a place
+    pub fn or_at(self, span: Span) -> SpanError {
+        SpanError {
+            span: self.span.or(Some(span)),
+            ..self
+        }
+    }
+
+    /// Message, then `input` with '^' under the span
+    pub fn render(&self, input: &str) -> String {
+        let Some(span) = self.span else {
+            return self.error.to_string();
+        };
+        let start = span.start.min(input.len());
+        let end = span.end.clamp(start, input.len());
+        let column = input[..start].chars().count();
+        let width = input[start..end].chars().count().max(1);
+        format!(
+            "{}\n{}\n{}{}",
+            self.error,
+            input,
+            " ".repeat(column),
+            "^".repeat(width)
+        )
+    }
+}
+
+impl From<Error> for SpanError {
+    fn from(error: Error) -> Self {
+        SpanError { error, span: None }
+    }
+}
+
+/// Bytes are counted from 1, without the input the column in chars isn't known,
+/// `render` shows it
+impl Display for SpanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "{} at byte {}", self.error, span.start + 1),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
+impl std::error::Error for SpanError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Span, SpanError};
+    use crate::error::Error;
+
+    #[test]
+    fn render() {
+        let error = SpanError::new(Error::DivisionByZero, Span::new(2, 3));
+        assert_eq!("Division by zero\n1 / 0\n  ^", error.render("1 / 0"));
+        assert_eq!("Division by zero at byte 3", error.to_string());
+
+        let error = SpanError::new(Error::MissingOperand(None), Span::new(3, 3));
+        assert_eq!("Missing operand at the end\n2 +\n   ^", error.render("2 +"));
+        let error = SpanError::from(Error::UnknownVariable("é".to_string())).or_at(Span::new(4, 6));
+        assert_eq!("Unknown variable 'é'\n1 + é\n    ^", error.render("1 + é"));
+
+        // π takes two bytes, so the byte and the column differ
+        let error = SpanError::new(Error::UnexpectedChar('$'), Span::new(4, 5));
+        assert_eq!("Unexpected token '$' at byte 5", error.to_string());
+        assert_eq!("Unexpected token '$'\nπ= $\n   ^", error.render("π= $"));
+    }
+}
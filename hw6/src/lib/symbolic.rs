@@ -0,0 +1,447 @@
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+
+use crate::{
+    error::Error,
+    expr::Expr,
+    number::Number,
+    token::{Function, Literal, Operator},
+};
+
+impl Expr {
+    /// Exact value of an expression without variables, `None` if it has no rational one
+    pub fn constant(&self) -> Option<BigRational> {
+        match self {
+            Expr::Number(x) => BigRational::from_literal(x).ok(),
+            Expr::Variable(_) | Expr::Assign(_, _) | Expr::Conditional(_, _, _) => None,
+            Expr::Unary(Operator::UnaryMinus, x) => Some(-x.constant()?),
+            Expr::Unary(Operator::UnaryPlus, x) => x.constant(),
+            Expr::Unary(_, _) => None,
+            Expr::Binary(op, x1, x2) => {
+                let (x1, x2) = (x1.constant()?, x2.constant()?);
+                match op {
+                    Operator::Plus => Number::add(x1, x2),
+                    Operator::Minus => Number::sub(x1, x2),
+                    Operator::Star => Number::mul(x1, x2),
+                    Operator::Slash => Number::div(x1, x2),
+                    Operator::Percent => Number::rem(x1, x2),
+                    Operator::Caret => Number::pow(x1, x2),
+                    _ => return None,
+                }
+                .ok()
+            }
+            Expr::Call(function, args) => {
+                let args = args.iter().map(Expr::constant).collect::<Option<_>>()?;
+                BigRational::call(*function, args).ok()
+            }
+        }
+    }
+
+    /// Whether `variable` is in the expression
+    pub fn depends_on(&self, variable: &str) -> bool {
+        match self {
+            Expr::Number(_) => false,
+            Expr::Variable(name) => name == variable,
+            Expr::Unary(_, x) | Expr::Assign(_, x) => x.depends_on(variable),
+            Expr::Binary(_, x1, x2) => x1.depends_on(variable) || x2.depends_on(variable),
+            Expr::Call(_, args) => args.iter().any(|x| x.depends_on(variable)),
+            Expr::Conditional(c, x1, x2) => {
+                c.depends_on(variable) || x1.depends_on(variable) || x2.depends_on(variable)
+            }
+        }
+    }
+
+    /// Folds constants, drops identities like `x * 1` and `x + 0`, collects like terms
+    pub fn simplify(&self) -> Expr {
+        if let Some(x) = self.constant() {
+            return number(x);
+        }
+        match self {
+            Expr::Number(_) | Expr::Variable(_) => self.clone(),
+            Expr::Unary(Operator::UnaryMinus, _)
+            | Expr::Binary(Operator::Plus | Operator::Minus, _, _) => {
+                let mut sum = Sum::default();
+                sum.add(self, BigRational::one());
+                sum.build()
+            }
+            Expr::Unary(Operator::UnaryPlus, x) => x.simplify(),
+            Expr::Unary(op, x) => Expr::Unary(op.clone(), Box::new(x.simplify())),
+            Expr::Binary(Operator::Star, x1, x2) => product(x1.simplify(), x2.simplify()),
+            Expr::Binary(Operator::Slash, x1, x2) => quotient(x1.simplify(), x2.simplify()),
+            Expr::Binary(Operator::Caret, x1, x2) => power(x1.simplify(), x2.simplify()),
+            Expr::Binary(op, x1, x2) => binary(op.clone(), x1.simplify(), x2.simplify()),
+            Expr::Call(function, args) => {
+                Expr::Call(*function, args.iter().map(Expr::simplify).collect())
+            }
+            Expr::Conditional(c, x1, x2) => Expr::Conditional(
+                Box::new(c.simplify()),
+                Box::new(x1.simplify()),
+                Box::new(x2.simplify()),
+            ),
+            Expr::Assign(name, x) => Expr::Assign(name.clone(), Box::new(x.simplify())),
+        }
+    }
+
+    /// Derivative by `variable`, not simplified.
+    /// '%', max, min, round, comparisons and logical operators have none
+    pub fn derivative(&self, variable: &str) -> Result<Expr, Error> {
+        let d = |x: &Expr| x.derivative(variable);
+        if let Expr::Assign(_, _) = self {
+            return Err(Error::NotDifferentiable("an assignment".to_string()));
+        }
+        if !self.depends_on(variable) {
+            return Ok(integer(0));
+        }
+        Ok(match self {
+            Expr::Variable(_) => integer(1),
+            Expr::Unary(Operator::Not, _) => {
+                return Err(Error::NotDifferentiable("'!'".to_string()))
+            }
+            Expr::Unary(op, x) => Expr::Unary(op.clone(), Box::new(d(x)?)),
+            Expr::Binary(op @ (Operator::Plus | Operator::Minus), x1, x2) => {
+                binary(op.clone(), d(x1)?, d(x2)?)
+            }
+            Expr::Binary(Operator::Star, x1, x2) => binary(
+                Operator::Plus,
+                binary(Operator::Star, d(x1)?, (**x2).clone()),
+                binary(Operator::Star, (**x1).clone(), d(x2)?),
+            ),
+            Expr::Binary(Operator::Slash, x1, x2) => binary(
+                Operator::Slash,
+                binary(
+                    Operator::Minus,
+                    binary(Operator::Star, d(x1)?, (**x2).clone()),
+                    binary(Operator::Star, (**x1).clone(), d(x2)?),
+                ),
+                binary(Operator::Caret, (**x2).clone(), integer(2)),
+            ),
+            Expr::Binary(Operator::Caret, x1, x2) => {
+                let (base, exponent) = ((**x1).clone(), (**x2).clone());
+                if !exponent.depends_on(variable) {
+                    // n * x ^ (n - 1) * x'
+                    let lower = binary(Operator::Minus, exponent.clone(), integer(1));
+                    binary(
+                        Operator::Star,
+                        binary(
+                            Operator::Star,
+                            exponent,
+                            binary(Operator::Caret, base, lower),
+                        ),
+                        d(x1)?,
+                    )
+                } else {
+                    // x ^ y * (y' * ln(x) + y * x' / x)
+                    let ln = Expr::Call(Function::Ln, vec![base.clone()]);
+                    let inner = binary(
+                        Operator::Plus,
+                        binary(Operator::Star, d(x2)?, ln),
+                        binary(
+                            Operator::Slash,
+                            binary(Operator::Star, exponent, d(x1)?),
+                            base,
+                        ),
+                    );
+                    binary(Operator::Star, self.clone(), inner)
+                }
+            }
+            Expr::Binary(op, _, _) => return Err(Error::NotDifferentiable(format!("'{}'", op))),
+            Expr::Call(function, args) => {
+                let x = args[0].clone();
+                let call = |function: Function| Expr::Call(function, vec![x.clone()]);
+                let dx = || d(&x);
+                match function {
+                    Function::Abs => binary(
+                        Operator::Slash,
+                        binary(Operator::Star, dx()?, x.clone()),
+                        call(Function::Abs),
+                    ),
+                    Function::Sqrt => binary(
+                        Operator::Slash,
+                        dx()?,
+                        binary(Operator::Star, integer(2), call(Function::Sqrt)),
+                    ),
+                    Function::Sin => binary(Operator::Star, call(Function::Cos), dx()?),
+                    Function::Cos => binary(
+                        Operator::Star,
+                        Expr::Unary(Operator::UnaryMinus, Box::new(call(Function::Sin))),
+                        dx()?,
+                    ),
+                    Function::Ln => binary(Operator::Slash, dx()?, x.clone()),
+                    // log(b, x) = ln(x) / ln(b)
+                    Function::Log => {
+                        let ln = |x: &Expr| Expr::Call(Function::Ln, vec![x.clone()]);
+                        return d(&binary(Operator::Slash, ln(&args[1]), ln(&args[0])));
+                    }
+                    Function::Max | Function::Min | Function::Round => {
+                        return Err(Error::NotDifferentiable(format!("'{}'", function.name())))
+                    }
+                }
+            }
+            // the derivative of the branch that is taken
+            Expr::Conditional(c, x1, x2) => {
+                Expr::Conditional(c.clone(), Box::new(d(x1)?), Box::new(d(x2)?))
+            }
+            Expr::Number(_) | Expr::Assign(_, _) => unreachable!("they are done above"),
+        })
+    }
+}
+
+fn binary(op: Operator, x1: Expr, x2: Expr) -> Expr {
+    Expr::Binary(op, Box::new(x1), Box::new(x2))
+}
+
+fn integer(x: u32) -> Expr {
+    Expr::Number(x.into())
+}
+
+/// Shortest expression of an exact value: integer, decimal or fraction
+fn number(x: BigRational) -> Expr {
+    if x.is_negative() {
+        return Expr::Unary(Operator::UnaryMinus, Box::new(number(-x)));
+    }
+    let literal = |digits: String, exponent| {
+        Expr::Number(Literal::new(&digits, exponent).expect("exponents of numbers are small"))
+    };
+    let mut scaled = x.clone();
+    for exponent in 0..=64 {
+        if scaled.is_integer() {
+            return literal(scaled.to_integer().to_string(), -exponent);
+        }
+        scaled *= BigRational::from_integer(10.into());
+    }
+    binary(
+        Operator::Slash,
+        literal(x.numer().to_string(), 0),
+        literal(x.denom().to_string(), 0),
+    )
+}
+
+/// Splits a simplified expression to a coefficient and the rest, `None` if it's constant
+fn split(x: Expr) -> (BigRational, Option<Expr>) {
+    if let Some(c) = x.constant() {
+        return (c, None);
+    }
+    match &x {
+        Expr::Unary(Operator::UnaryMinus, x) => {
+            let (k, x) = split((**x).clone());
+            return (-k, x);
+        }
+        Expr::Binary(Operator::Star, x1, x2) => {
+            if let Some(c) = x1.constant() {
+                return (c, Some((**x2).clone()));
+            }
+            if let Some(c) = x2.constant() {
+                return (c, Some((**x1).clone()));
+            }
+            // like `2 * x * y`
+            if let (k, Some(x1)) = split((**x1).clone()) {
+                if !k.is_one() {
+                    return (k, Some(binary(Operator::Star, x1, (**x2).clone())));
+                }
+            }
+        }
+        _ => {}
+    }
+    (BigRational::one(), Some(x))
+}
+
+/// `c * x` with the coefficients of `x` multiplied in
+fn scale(c: BigRational, x: Expr) -> Expr {
+    match split(x) {
+        (k, None) => number(c * k),
+        (k, Some(x)) => term(c * k, x),
+    }
+}
+
+/// `c * x` for `x` without a coefficient, the coefficient goes before all factors
+fn term(c: BigRational, x: Expr) -> Expr {
+    if c.is_zero() {
+        integer(0)
+    } else if c.is_one() {
+        x
+    } else if let Expr::Binary(Operator::Star, x1, x2) = x {
+        binary(Operator::Star, term(c, *x1), *x2)
+    } else if c == -BigRational::one() {
+        Expr::Unary(Operator::UnaryMinus, Box::new(x))
+    } else {
+        binary(Operator::Star, number(c), x)
+    }
+}
+
+fn product(x1: Expr, x2: Expr) -> Expr {
+    match (x1.constant(), x2.constant()) {
+        (Some(c), _) => scale(c, x2),
+        (_, Some(c)) => scale(c, x1),
+        _ => {
+            let ((k1, x1), (k2, x2)) = (split(x1), split(x2));
+            let (x1, x2) = (x1.expect("not constant"), x2.expect("not constant"));
+            // factors in the same order make like terms of `x * y` and `y * x`
+            let x = if x1 == x2 {
+                binary(Operator::Caret, x1, integer(2))
+            } else if x1.infix() <= x2.infix() {
+                binary(Operator::Star, x1, x2)
+            } else {
+                binary(Operator::Star, x2, x1)
+            };
+            term(k1 * k2, x)
+        }
+    }
+}
+
+fn quotient(x1: Expr, x2: Expr) -> Expr {
+    match x2.constant() {
+        Some(c) if c.is_one() => x1,
+        Some(c) if c == -BigRational::one() => scale(c, x1),
+        _ => binary(Operator::Slash, x1, x2),
+    }
+}
+
+fn power(x1: Expr, x2: Expr) -> Expr {
+    match (x1.constant(), x2.constant()) {
+        (_, Some(c)) if c.is_zero() => integer(1),
+        (_, Some(c)) if c.is_one() => x1,
+        (Some(c), _) if c.is_one() => integer(1),
+        _ => binary(Operator::Caret, x1, x2),
+    }
+}
+
+/// Sum of terms with coefficients, like terms are added up
+#[derive(Default)]
+struct Sum {
+    terms: Vec<(BigRational, Expr)>,
+    constant: BigRational,
+}
+
+impl Sum {
+    /// Adds `x` times `sign`, opening its sums
+    fn add(&mut self, x: &Expr, sign: BigRational) {
+        match x {
+            Expr::Binary(Operator::Plus, x1, x2) => {
+                self.add(x1, sign.clone());
+                self.add(x2, sign);
+            }
+            Expr::Binary(Operator::Minus, x1, x2) => {
+                self.add(x1, sign.clone());
+                self.add(x2, -sign);
+            }
+            Expr::Unary(Operator::UnaryMinus, x) => self.add(x, -sign),
+            Expr::Unary(Operator::UnaryPlus, x) => self.add(x, sign),
+            _ => {
+                let x = x.simplify();
+                // like `(a + b) * 1`, '!' is a term of its own
+                if matches!(
+                    x,
+                    Expr::Binary(Operator::Plus | Operator::Minus, _, _)
+                        | Expr::Unary(Operator::UnaryMinus | Operator::UnaryPlus, _)
+                ) {
+                    return self.add(&x, sign);
+                }
+                match split(x) {
+                    (k, None) => self.constant += sign * k,
+                    (k, Some(x)) => match self.terms.iter_mut().find(|(_, y)| *y == x) {
+                        Some((c, _)) => *c += sign * k,
+                        None => self.terms.push((sign * k, x)),
+                    },
+                }
+            }
+        }
+    }
+
+    /// Terms in the order they first appear, then the constant
+    fn build(self) -> Expr {
+        let terms = self.terms.into_iter().filter(|(c, _)| !c.is_zero());
+        let constant = (!self.constant.is_zero()).then_some((self.constant, None));
+        let mut res = None;
+        for (c, x) in terms.map(|(c, x)| (c, Some(x))).chain(constant) {
+            let part = |c: BigRational| match x {
+                Some(x) => term(c, x),
+                None => number(c),
+            };
+            res = Some(match res {
+                None => part(c),
+                Some(sum) if c.is_negative() => binary(Operator::Minus, sum, part(-c)),
+                Some(sum) => binary(Operator::Plus, sum, part(c)),
+            });
+        }
+        res.unwrap_or_else(|| integer(0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{error::Error, visitors::ast_visitor::test::tree};
+
+    fn check_simplify(input: &str, expected: &str) {
+        assert_eq!(expected, tree(input).simplify().infix());
+    }
+
+    fn check_derivative(input: &str, expected: &str) {
+        let derivative = tree(input).derivative("x").unwrap();
+        assert_eq!(expected, derivative.simplify().infix());
+    }
+
+    #[test]
+    fn constants() {
+        check_simplify("1 + 2 * 3", "7");
+        check_simplify("0.1 + 0.2", "0.3");
+        check_simplify("1 / 3 + 1", "4 / 3");
+        check_simplify("-(2 ^ 2) * x", "-4 * x");
+        check_simplify("sqrt(2) * (1 + 1)", "2 * sqrt(2)");
+        check_simplify("x / 0", "x / 0");
+    }
+
+    #[test]
+    fn identities() {
+        check_simplify("x * 1 + 0", "x");
+        check_simplify("0 * sin(x) + y", "y");
+        check_simplify("x ^ 1 + y ^ 0", "x + 1");
+        check_simplify("(x + y) / 1", "x + y");
+        check_simplify("- -x", "x");
+        check_simplify("a = x * (2 - 1)", "a = x");
+        check_simplify("x + x > 2 * 1 && !(y * 1 == 0)", "2 * x > 2 && !(y == 0)");
+        check_simplify("1 < 2 ? x + 0 : +y", "1 < 2 ? x : y");
+        check_simplify("x * (1 + !y)", "(!y + 1) * x");
+        check_simplify("!y - 2 * !y + 1", "-!y + 1");
+        check_derivative("x * (1 + !y)", "!y + 1");
+    }
+
+    #[test]
+    fn like_terms() {
+        check_simplify("x + x", "2 * x");
+        check_simplify("2 * x + y - x * 3 + 1", "-x + y + 1");
+        check_simplify("x - x", "0");
+        check_simplify("x * y - (y * x - x * y)", "x * y");
+        check_simplify("2 * x * (3 * x)", "6 * x ^ 2");
+        check_simplify("1 - (x + 1)", "-x");
+    }
+
+    #[test]
+    fn derivatives() {
+        check_derivative("x ^ 2 + 3 * x + 1", "2 * x + 3");
+        check_derivative("y * x", "y");
+        check_derivative("sin(x) * x", "cos(x) * x + sin(x)");
+        check_derivative("1 / x", "-1 / x ^ 2");
+        check_derivative("ln(x ^ 2)", "2 * x / x ^ 2");
+        check_derivative("2 ^ x", "2 ^ x * ln(2)");
+        check_derivative("sqrt(y)", "0");
+        check_derivative("x > 0 ? x ^ 2 : -x", "x > 0 ? 2 * x : -1");
+    }
+
+    #[test]
+    fn not_differentiable() {
+        assert_eq!(
+            Err(Error::NotDifferentiable("'max'".to_string())),
+            tree("max(x, 1)").derivative("x")
+        );
+        assert_eq!(
+            Err(Error::NotDifferentiable("'%'".to_string())),
+            tree("x % 2").derivative("x")
+        );
+        assert!(tree("y = x").derivative("x").is_err());
+        assert_eq!(
+            Err(Error::NotDifferentiable("'<'".to_string())),
+            tree("x < 1").derivative("x")
+        );
+        assert!(tree("!(x > 1)").derivative("x").is_err());
+    }
+}
@@ -1,8 +1,158 @@
+use std::{fmt::Display, ops::Neg};
+
+use crate::{
+    error::Error,
+    span::Span,
+    visitors::combinators::{Inspect, Map, Pair},
+};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Token {
     Paren(Paren),
-    Number(u32),
+    Number(Literal),
     Operator(Operator),
+    Comma,
+    /// Name of a variable or a function
+    Identifier(String),
+    /// '=' of an assignment
+    Equals,
+    /// Call of a function with the given number of arguments, made by the parser
+    Function(Function, usize),
+    /// Saving of the computed value to a variable, made by the parser
+    Assign(String),
+    /// '?' of a conditional, in RPN it follows the condition
+    /// and the branch after it is skipped if the condition is false
+    Question,
+    /// ':' of a conditional, in RPN it follows the first branch
+    /// and the second one is skipped if the first one was taken
+    Colon,
+    /// Made by the parser after the left operand of `&&` or `||`,
+    /// the right operand is skipped if the left one decides the result
+    ShortCircuit(Operator),
+}
+
+/// Exact value of a number literal, `±digits * 10^exponent`.
+/// It's converted to a number type only by the visitor that computes.
+/// Only the parser makes negative literals, of a '-' right before a number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Literal {
+    /// Without leading and trailing zeros, "0" for zero
+    digits: String,
+    exponent: i64,
+    /// Never true for zero
+    negative: bool,
+}
+
+impl Literal {
+    /// `digits` must be ASCII digits, the trailing zeros mustn't take the exponent out of i64
+    pub fn new(digits: &str, exponent: i64) -> Result<Literal, Error> {
+        let digits = digits.trim_start_matches('0');
+        let trimmed = digits.trim_end_matches('0');
+        if trimmed.is_empty() {
+            return Ok(Literal {
+                digits: "0".to_string(),
+                exponent: 0,
+                negative: false,
+            });
+        }
+        let zeros = (digits.len() - trimmed.len()) as i64;
+        let exponent = exponent
+            .checked_add(zeros)
+            .ok_or_else(|| Error::TooBig(format!("Exponent of '{}e{}'", digits, exponent)))?;
+        Ok(Literal {
+            digits: trimmed.to_string(),
+            exponent,
+            negative: false,
+        })
+    }
+
+    /// Parses decimal and scientific literals: "12", "1.5", ".5", "5.", "1e3", "2.5E-3"
+    pub fn parse(s: &str) -> Result<Literal, Error> {
+        let malformed = || Error::MalformedNumber(s.to_string());
+        let too_big = || Error::TooBig(format!("Exponent of '{}'", s));
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(i) => {
+                let exponent = &s[i + 1..];
+                let unsigned = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+                if unsigned.is_empty() || !unsigned.chars().all(|c| c.is_ascii_digit()) {
+                    return Err(malformed());
+                }
+                let exponent = exponent.parse::<i64>().map_err(|_| too_big())?;
+                (&s[..i], exponent)
+            }
+            None => (s, 0),
+        };
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if int.len() + frac.len() == 0
+            || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+        {
+            return Err(malformed());
+        }
+        let exponent = exponent
+            .checked_sub(frac.len() as i64)
+            .ok_or_else(too_big)?;
+        Literal::new(&format!("{}{}", int, frac), exponent).map_err(|_| too_big())
+    }
+
+    pub fn digits(&self) -> &str {
+        &self.digits
+    }
+
+    pub fn exponent(&self) -> i64 {
+        self.exponent
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn abs(self) -> Literal {
+        Literal {
+            negative: false,
+            ..self
+        }
+    }
+}
+
+/// Zero stays zero
+impl Neg for Literal {
+    type Output = Literal;
+
+    fn neg(self) -> Literal {
+        Literal {
+            negative: !self.negative && self.digits != "0",
+            ..self
+        }
+    }
+}
+
+impl From<u32> for Literal {
+    fn from(x: u32) -> Self {
+        Literal::new(&x.to_string(), 0).expect("integers have small exponents")
+    }
+}
+
+impl Display for Literal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let digits = &self.digits;
+        // digits before the decimal point, i128 keeps it from overflowing at the edges of i64
+        let point = digits.len() as i128 + self.exponent as i128;
+        if self.exponent >= 0 && point <= 21 {
+            write!(f, "{}{}", digits, "0".repeat(self.exponent as usize))
+        } else if self.exponent < 0 && point > 0 {
+            let (int, frac) = digits.split_at(point as usize);
+            write!(f, "{}.{}", int, frac)
+        } else if self.exponent < 0 && point > -6 {
+            write!(f, "0.{}{}", "0".repeat(-point as usize), digits)
+        } else if digits.len() == 1 {
+            write!(f, "{}e{}", digits, point - 1)
+        } else {
+            write!(f, "{}.{}e{}", &digits[..1], &digits[1..], point - 1)
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -11,21 +161,177 @@ pub enum Operator {
     Minus,
     Star,
     Slash,
+    Percent,
+    Caret,
+    /// Made of `Minus` by the parser when it stands before an operand
+    UnaryMinus,
+    /// Made of `Plus` by the parser when it stands before an operand
+    UnaryPlus,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    EqualEqual,
+    NotEqual,
+    And,
+    Or,
+    Not,
+    /// `c ? a : b`, made by the parser at the end of the second branch
+    Conditional,
 }
 
 impl Operator {
     pub fn left_associative(&self) -> bool {
-        true
+        !matches!(
+            self,
+            Operator::Caret
+                | Operator::UnaryMinus
+                | Operator::UnaryPlus
+                | Operator::Not
+                | Operator::Conditional
+        )
     }
 
     pub fn precedence(&self) -> u8 {
         match self {
-            Operator::Plus => 1,
-            Operator::Minus => 1,
-            Operator::Star => 2,
-            Operator::Slash => 2,
+            Operator::Conditional => 1,
+            Operator::Or => 2,
+            Operator::And => 3,
+            Operator::EqualEqual => 4,
+            Operator::NotEqual => 4,
+            Operator::Less => 5,
+            Operator::LessEqual => 5,
+            Operator::Greater => 5,
+            Operator::GreaterEqual => 5,
+            Operator::Plus => 6,
+            Operator::Minus => 6,
+            Operator::Star => 7,
+            Operator::Slash => 7,
+            Operator::Percent => 7,
+            Operator::UnaryMinus => 8,
+            Operator::UnaryPlus => 8,
+            Operator::Not => 8,
+            Operator::Caret => 9,
         }
     }
+
+    /// Number of operands
+    pub fn arity(&self) -> usize {
+        match self {
+            Operator::UnaryMinus | Operator::UnaryPlus | Operator::Not => 1,
+            Operator::Conditional => 3,
+            _ => 2,
+        }
+    }
+
+    /// Unary variant of the operator if it has one
+    pub fn to_unary(&self) -> Option<Operator> {
+        match self {
+            Operator::Plus => Some(Operator::UnaryPlus),
+            Operator::Minus => Some(Operator::UnaryMinus),
+            Operator::Not => Some(Operator::Not),
+            _ => None,
+        }
+    }
+
+    /// Whether the operands and the result are booleans
+    pub fn is_logical(&self) -> bool {
+        matches!(self, Operator::And | Operator::Or | Operator::Not)
+    }
+}
+
+/// Spelling in infix notation
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Operator::Plus | Operator::UnaryPlus => "+",
+            Operator::Minus | Operator::UnaryMinus => "-",
+            Operator::Star => "*",
+            Operator::Slash => "/",
+            Operator::Percent => "%",
+            Operator::Caret => "^",
+            Operator::Less => "<",
+            Operator::LessEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterEqual => ">=",
+            Operator::EqualEqual => "==",
+            Operator::NotEqual => "!=",
+            Operator::And => "&&",
+            Operator::Or => "||",
+            Operator::Not => "!",
+            Operator::Conditional => "?:",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Max,
+    Min,
+    Abs,
+    Sqrt,
+    Sin,
+    Cos,
+    Ln,
+    /// log(base, x)
+    Log,
+    /// round(x) or round(x, digits after the decimal point)
+    Round,
+}
+
+impl Function {
+    pub const ALL: [Function; 9] = [
+        Function::Max,
+        Function::Min,
+        Function::Abs,
+        Function::Sqrt,
+        Function::Sin,
+        Function::Cos,
+        Function::Ln,
+        Function::Log,
+        Function::Round,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Function::Max => "max",
+            Function::Min => "min",
+            Function::Abs => "abs",
+            Function::Sqrt => "sqrt",
+            Function::Sin => "sin",
+            Function::Cos => "cos",
+            Function::Ln => "ln",
+            Function::Log => "log",
+            Function::Round => "round",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Function> {
+        Function::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    /// Least and most number of arguments, `None` for any number
+    pub fn arity(&self) -> (usize, Option<usize>) {
+        match self {
+            Function::Max | Function::Min => (1, None),
+            Function::Log => (2, Some(2)),
+            Function::Round => (1, Some(2)),
+            _ => (1, Some(1)),
+        }
+    }
+
+    /// Error for a call with wrong number of arguments
+    pub fn check_arity(&self, args: usize) -> Result<(), Error> {
+        let (min, max) = self.arity();
+        if args < min || max.is_some_and(|max| args > max) {
+            return Err(Error::Arity {
+                function: *self,
+                args,
+            });
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -38,11 +344,18 @@ pub trait TokenListVisitor: Sized {
     type Output;
     type Error: Sized;
 
-    fn new() -> Self;
     fn visit_left(&mut self) -> Result<(), Self::Error>;
     fn visit_right(&mut self) -> Result<(), Self::Error>;
-    fn visit_number(&mut self, x: u32) -> Result<(), Self::Error>;
+    fn visit_number(&mut self, x: Literal) -> Result<(), Self::Error>;
     fn visit_op(&mut self, op: Operator) -> Result<(), Self::Error>;
+    fn visit_comma(&mut self) -> Result<(), Self::Error>;
+    fn visit_identifier(&mut self, name: String) -> Result<(), Self::Error>;
+    fn visit_function(&mut self, function: Function, args: usize) -> Result<(), Self::Error>;
+    fn visit_equals(&mut self) -> Result<(), Self::Error>;
+    fn visit_assign(&mut self, name: String) -> Result<(), Self::Error>;
+    fn visit_question(&mut self) -> Result<(), Self::Error>;
+    fn visit_colon(&mut self) -> Result<(), Self::Error>;
+    fn visit_short_circuit(&mut self, op: Operator) -> Result<(), Self::Error>;
     fn visit(&mut self, token: Token) -> Result<(), Self::Error> {
         match token {
             Token::Paren(p) => match p {
@@ -51,14 +364,147 @@ pub trait TokenListVisitor: Sized {
             },
             Token::Number(x) => self.visit_number(x),
             Token::Operator(op) => self.visit_op(op),
+            Token::Comma => self.visit_comma(),
+            Token::Identifier(name) => self.visit_identifier(name),
+            Token::Function(function, args) => self.visit_function(function, args),
+            Token::Equals => self.visit_equals(),
+            Token::Assign(name) => self.visit_assign(name),
+            Token::Question => self.visit_question(),
+            Token::Colon => self.visit_colon(),
+            Token::ShortCircuit(op) => self.visit_short_circuit(op),
         }
     }
+    /// Visits `token` that came from `span` of the input,
+    /// visitors that report places of errors keep the span
+    fn visit_at(&mut self, token: Token, _span: Span) -> Result<(), Self::Error> {
+        self.visit(token)
+    }
     fn get_result(self) -> Result<Self::Output, Self::Error>;
-    fn visit_tokens(tokens: impl IntoIterator<Item = Token>) -> Result<Self::Output, Self::Error> {
-        let mut visitor = Self::new();
+
+    /// Visits all `tokens` and gives the result
+    fn run_tokens(
+        mut self,
+        tokens: impl IntoIterator<Item = Token>,
+    ) -> Result<Self::Output, Self::Error> {
+        for token in tokens {
+            self.visit(token)?;
+        }
+        self.get_result()
+    }
+    fn run_spanned(
+        mut self,
+        tokens: impl IntoIterator<Item = (Token, Span)>,
+    ) -> Result<Self::Output, Self::Error> {
+        for (token, span) in tokens {
+            self.visit_at(token, span)?;
+        }
+        self.get_result()
+    }
+    /// Visits tokens of a source that can fail, like `Tokenizer::chars`,
+    /// until the first error of the source or the visitor
+    fn try_run_spanned<E>(
+        mut self,
+        tokens: impl IntoIterator<Item = Result<(Token, Span), E>>,
+    ) -> Result<Self::Output, Self::Error>
+    where
+        Self::Error: From<E>,
+    {
         for token in tokens {
-            visitor.visit(token)?;
+            let (token, span) = token?;
+            self.visit_at(token, span)?;
+        }
+        self.get_result()
+    }
+
+    fn visit_tokens(tokens: impl IntoIterator<Item = Token>) -> Result<Self::Output, Self::Error>
+    where
+        Self: Default,
+    {
+        Self::default().run_tokens(tokens)
+    }
+    fn visit_spanned(
+        tokens: impl IntoIterator<Item = (Token, Span)>,
+    ) -> Result<Self::Output, Self::Error>
+    where
+        Self: Default,
+    {
+        Self::default().run_spanned(tokens)
+    }
+    fn try_visit_spanned<E>(
+        tokens: impl IntoIterator<Item = Result<(Token, Span), E>>,
+    ) -> Result<Self::Output, Self::Error>
+    where
+        Self: Default,
+        Self::Error: From<E>,
+    {
+        Self::default().try_run_spanned(tokens)
+    }
+
+    /// Visitor that gives every token to this one and then to `other`
+    fn pair<V>(self, other: V) -> Pair<Self, V>
+    where
+        V: TokenListVisitor,
+        V::Error: Into<Self::Error>,
+    {
+        Pair::new(self, other)
+    }
+    /// Visitor with the result changed by `f`
+    fn map<O, F: FnOnce(Self::Output) -> O>(self, f: F) -> Map<Self, F> {
+        Map::new(self, f)
+    }
+    /// Visitor that shows every token to `f` before visiting it
+    fn inspect<F: FnMut(&Token)>(self, f: F) -> Inspect<Self, F> {
+        Inspect::new(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Literal;
+    use crate::error::Error;
+
+    #[test]
+    fn parse_literals() {
+        let parse = |s| Literal::parse(s).unwrap();
+        let new = |digits, exponent| Literal::new(digits, exponent).unwrap();
+        assert_eq!(new("15", -1), parse("1.5"));
+        assert_eq!(new("5", -1), parse(".5"));
+        assert_eq!(new("5", 0), parse("5."));
+        assert_eq!(new("25", -4), parse("2.5E-3"));
+        assert_eq!(new("1", 3), parse("1000"));
+        assert_eq!(parse("0"), parse("000.000e+12"));
+        for bad in [".", "1e", "1e+", "1.2.3", "e5", "1e5.0"] {
+            assert!(Literal::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn display() {
+        let show = |s| Literal::parse(s).unwrap().to_string();
+        assert_eq!("1500", show("1.5e3"));
+        assert_eq!("0.0025", show("2.5e-3"));
+        assert_eq!("12.5", show("12.50"));
+        assert_eq!("0", show("0.0"));
+        assert_eq!("1e30", show("1e30"));
+        assert_eq!("1.25e-10", show("125e-12"));
+        assert_eq!("-0.5", (-Literal::parse("0.5").unwrap()).to_string());
+        assert_eq!("0", (-Literal::parse("0").unwrap()).to_string());
+    }
+
+    #[test]
+    fn edge_exponents() {
+        let show = |s| Literal::parse(s).unwrap().to_string();
+        assert_eq!("1e9223372036854775807", show("1e9223372036854775807"));
+        assert_eq!("1.5e9223372036854775807", show("15e9223372036854775806"));
+        assert_eq!("1e-9223372036854775808", show("1e-9223372036854775808"));
+        assert_eq!("1.5e-9223372036854775807", show("1.5e-9223372036854775807"));
+        for big in ["10e9223372036854775807", "1.5e-9223372036854775808"] {
+            assert!(
+                matches!(Literal::parse(big), Err(Error::TooBig(_))),
+                "{}",
+                big
+            );
         }
-        visitor.get_result()
+        assert!(Literal::new("10", i64::MAX).is_err());
     }
 }
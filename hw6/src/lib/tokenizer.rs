@@ -1,34 +1,62 @@
-use crate::token::{self, Token};
+use std::{collections::VecDeque, io::BufRead};
 
-type RVoid = Result<(), String>;
+use crate::{
+    error::Error,
+    span::{Span, SpanError},
+    token::{self, Literal, Token},
+};
+
+type RVoid = Result<(), SpanError>;
 
 enum TokenizerState {
     WhiteSpace,
     Operator,
     Number(String),
+    Identifier(String),
+    /// First char of an operator that may have two, like '<' of "<="
+    Symbol(char),
     Paren,
     Start,
 }
 
 pub struct Tokenizer {
-    tokens: Vec<Token>,
+    /// Tokens made of the chars so far, not taken yet
+    tokens: VecDeque<(Token, Span)>,
     state: TokenizerState,
+    /// Byte where the current number or identifier starts
+    start: usize,
+    /// Bytes of the current char
+    current: Span,
 }
 
 impl Tokenizer {
-    /// If the tokenizer's state is number then
-    /// try to parse it and push it into tokens list
+    /// If the tokenizer's state is number, identifier or the first char of an operator
+    /// then try to parse it and push it into tokens list
     fn end_number_if_necessary(&mut self) -> RVoid {
-        match &self.state {
+        let span = Span::new(self.start, self.current.start);
+        let token = match &self.state {
             TokenizerState::Number(x) => {
-                let num = x.parse::<u32>().map_err(|e| e.to_string())?;
-                self.tokens.push(Token::Number(num));
+                Token::Number(Literal::parse(x).map_err(|e| SpanError::new(e, span))?)
             }
-            _ => {}
-        }
+            TokenizerState::Identifier(name) => Token::Identifier(name.clone()),
+            TokenizerState::Symbol(c) => match c {
+                '<' => Token::Operator(token::Operator::Less),
+                '>' => Token::Operator(token::Operator::Greater),
+                '!' => Token::Operator(token::Operator::Not),
+                '=' => Token::Equals,
+                _ => return Err(SpanError::new(Error::UnexpectedChar(*c), span)),
+            },
+            _ => return Ok(()),
+        };
+        self.tokens.push_back((token, span));
         Ok(())
     }
 
+    /// Token of the current char
+    fn push(&mut self, token: Token) {
+        self.tokens.push_back((token, self.current));
+    }
+
     fn whitespace(&mut self) -> RVoid {
         self.end_number_if_necessary()?;
         self.state = TokenizerState::WhiteSpace;
@@ -37,60 +65,268 @@ impl Tokenizer {
     fn operation(&mut self, op: token::Operator) -> RVoid {
         self.end_number_if_necessary()?;
         self.state = TokenizerState::Operator;
-        self.tokens.push(Token::Operator(op));
+        self.push(Token::Operator(op));
         Ok(())
     }
+    /// Digit, '.' or exponent mark of a number
     fn digit(&mut self, digit: char) -> RVoid {
         match &mut self.state {
             TokenizerState::Number(s) => {
                 s.push(digit);
             }
             _ => {
+                self.end_number_if_necessary()?;
+                self.start = self.current.start;
                 self.state = TokenizerState::Number(digit.to_string());
             }
         }
         Ok(())
     }
+    /// Letter, digit or '_' of an identifier
+    fn letter(&mut self, letter: char) -> RVoid {
+        match &mut self.state {
+            TokenizerState::Identifier(s) => {
+                s.push(letter);
+            }
+            _ => {
+                self.end_number_if_necessary()?;
+                self.start = self.current.start;
+                self.state = TokenizerState::Identifier(letter.to_string());
+            }
+        }
+        Ok(())
+    }
+    /// First char of an operator that may have two
+    fn symbol(&mut self, c: char) -> RVoid {
+        self.end_number_if_necessary()?;
+        self.start = self.current.start;
+        self.state = TokenizerState::Symbol(c);
+        Ok(())
+    }
+    /// Second char of an operator, if `c` makes one with the first
+    fn second_symbol(&mut self, c: char) -> Option<token::Operator> {
+        let TokenizerState::Symbol(first) = self.state else {
+            return None;
+        };
+        let op = match (first, c) {
+            ('<', '=') => token::Operator::LessEqual,
+            ('>', '=') => token::Operator::GreaterEqual,
+            ('=', '=') => token::Operator::EqualEqual,
+            ('!', '=') => token::Operator::NotEqual,
+            ('&', '&') => token::Operator::And,
+            ('|', '|') => token::Operator::Or,
+            _ => return None,
+        };
+        self.state = TokenizerState::Operator;
+        Some(op)
+    }
+    /// ',', '?' or ':'
+    fn separator(&mut self, token: Token) -> RVoid {
+        self.end_number_if_necessary()?;
+        self.state = TokenizerState::Operator;
+        self.push(token);
+        Ok(())
+    }
     fn paren(&mut self, paren: token::Paren) -> RVoid {
         self.end_number_if_necessary()?;
         self.state = TokenizerState::Paren;
-        self.tokens.push(Token::Paren(paren));
+        self.push(Token::Paren(paren));
         Ok(())
     }
-    fn get_tokens(mut self) -> Result<Vec<Token>, String> {
+    /// Ends the last token at the end of the input
+    fn finish(&mut self) -> RVoid {
+        self.current = self.current.after();
         self.end_number_if_necessary()?;
-        return Ok(self.tokens);
+        self.state = TokenizerState::WhiteSpace;
+        Ok(())
+    }
+
+    /// '+' or '-' right after the exponent mark is the sign of the exponent
+    fn in_exponent(&self) -> bool {
+        matches!(&self.state, TokenizerState::Number(s) if s.ends_with(['e', 'E']))
+    }
+
+    fn in_number(&self) -> bool {
+        matches!(self.state, TokenizerState::Number(_))
+    }
+
+    fn in_identifier(&self) -> bool {
+        matches!(self.state, TokenizerState::Identifier(_))
     }
 
-    pub fn tokenize(input: String) -> Result<Vec<Token>, String> {
-        let mut tokenizer = Tokenizer {
-            tokens: vec![],
+    pub fn tokenize(input: String) -> Result<Vec<Token>, SpanError> {
+        let tokens = Tokenizer::tokenize_spanned(&input)?;
+        Ok(tokens.into_iter().map(|(token, _)| token).collect())
+    }
+
+    /// Tokens with the bytes of `input` they came from
+    pub fn tokenize_spanned(input: &str) -> Result<Vec<(Token, Span)>, SpanError> {
+        Tokenizer::chars(input.chars()).collect()
+    }
+
+    /// Tokens of `chars` made as they are read
+    pub fn chars(
+        chars: impl IntoIterator<Item = char>,
+    ) -> Tokens<impl Iterator<Item = Result<char, Error>>> {
+        Tokens::new(chars.into_iter().map(Ok))
+    }
+
+    /// Tokens of UTF-8 text of `reader` made as it's read
+    pub fn read<R: BufRead>(reader: R) -> Tokens<ReadChars<R>> {
+        Tokens::new(ReadChars { reader })
+    }
+
+    fn new() -> Tokenizer {
+        Tokenizer {
+            tokens: VecDeque::new(),
             state: TokenizerState::Start,
-        };
-        for char in input.chars() {
-            if char.is_digit(10) {
-                tokenizer.digit(char)?
-            } else if char.is_whitespace() {
-                tokenizer.whitespace()?
-            } else {
-                match char {
-                    '(' => tokenizer.paren(token::Paren::Left)?,
-                    ')' => tokenizer.paren(token::Paren::Right)?,
-                    '+' => tokenizer.operation(token::Operator::Plus)?,
-                    '-' => tokenizer.operation(token::Operator::Minus)?,
-                    '*' => tokenizer.operation(token::Operator::Star)?,
-                    '/' => tokenizer.operation(token::Operator::Slash)?,
-                    _ => return Err(format!("Unexpected token '{}'", char)),
+            start: 0,
+            current: Span::default(),
+        }
+    }
+
+    /// Adds the next char, it's at bytes `current`
+    fn feed(&mut self, char: char) -> RVoid {
+        if let Some(op) = self.second_symbol(char) {
+            let span = Span::new(self.start, self.current.end);
+            self.tokens.push_back((Token::Operator(op), span));
+            Ok(())
+        } else if char.is_alphanumeric() && self.in_identifier()
+            || char == '_'
+            || char.is_alphabetic() && !(matches!(char, 'e' | 'E') && self.in_number())
+        {
+            self.letter(char)
+        } else if char.is_ascii_digit()
+            || char == '.'
+            || matches!(char, 'e' | 'E') && self.in_number()
+            || matches!(char, '+' | '-') && self.in_exponent()
+        {
+            self.digit(char)
+        } else if char.is_whitespace() {
+            self.whitespace()
+        } else {
+            match char {
+                '(' => self.paren(token::Paren::Left),
+                ')' => self.paren(token::Paren::Right),
+                '+' => self.operation(token::Operator::Plus),
+                '-' => self.operation(token::Operator::Minus),
+                '*' => self.operation(token::Operator::Star),
+                '/' => self.operation(token::Operator::Slash),
+                '%' => self.operation(token::Operator::Percent),
+                '^' => self.operation(token::Operator::Caret),
+                ',' => self.separator(Token::Comma),
+                '?' => self.separator(Token::Question),
+                ':' => self.separator(Token::Colon),
+                '<' | '>' | '=' | '!' | '&' | '|' => self.symbol(char),
+                _ => Err(SpanError::new(Error::UnexpectedChar(char), self.current)),
+            }
+        }
+    }
+}
+
+/// Tokens of chars read one at a time, they end after the first error
+pub struct Tokens<I> {
+    chars: I,
+    tokenizer: Tokenizer,
+    /// Byte where the next char starts
+    offset: usize,
+    /// Error to give after the tokens before it
+    error: Option<SpanError>,
+    done: bool,
+}
+
+impl<I: Iterator<Item = Result<char, Error>>> Tokens<I> {
+    fn new(chars: I) -> Self {
+        Tokens {
+            chars,
+            tokenizer: Tokenizer::new(),
+            offset: 0,
+            error: None,
+            done: false,
+        }
+    }
+}
+
+impl<I: Iterator<Item = Result<char, Error>>> Iterator for Tokens<I> {
+    type Item = Result<(Token, Span), SpanError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(token) = self.tokenizer.tokens.pop_front() {
+                return Some(Ok(token));
+            }
+            if let Some(error) = self.error.take() {
+                return Some(Err(error));
+            }
+            if self.done {
+                return None;
+            }
+            let res = match self.chars.next() {
+                Some(Ok(char)) => {
+                    let end = self.offset + char.len_utf8();
+                    self.tokenizer.current = Span::new(self.offset, end);
+                    self.offset = end;
+                    self.tokenizer.feed(char)
                 }
+                Some(Err(error)) => Err(SpanError::new(error, Span::new(self.offset, self.offset))),
+                None => {
+                    self.done = true;
+                    self.tokenizer.finish()
+                }
+            };
+            if let Err(error) = res {
+                self.error = Some(error);
+                self.done = true;
             }
         }
-        tokenizer.get_tokens()
+    }
+}
+
+/// Chars of UTF-8 text read from a `BufRead` as they're needed
+pub struct ReadChars<R> {
+    reader: R,
+}
+
+impl<R: BufRead> Iterator for ReadChars<R> {
+    type Item = Result<char, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut bytes = [0; 4];
+        loop {
+            match self.reader.read(&mut bytes[..1]) {
+                Ok(0) => return None,
+                Ok(_) => break,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(Error::Input(e.to_string()))),
+            }
+        }
+        let invalid = || Error::Input("Invalid UTF-8".to_string());
+        let width = match bytes[0] {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => return Some(Err(invalid())),
+        };
+        if let Err(e) = self.reader.read_exact(&mut bytes[1..width]) {
+            return Some(Err(match e.kind() {
+                std::io::ErrorKind::UnexpectedEof => invalid(),
+                _ => Error::Input(e.to_string()),
+            }));
+        }
+        let char = std::str::from_utf8(&bytes[..width]).map_err(|_| invalid());
+        Some(char.map(|s| s.chars().next().expect("one char is decoded")))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::token::{Operator, Paren, Token};
+    use std::io::BufReader;
+
+    use crate::{
+        error::Error,
+        token::{Literal, Operator, Paren, Token},
+    };
 
     use super::Tokenizer;
 
@@ -116,11 +352,11 @@ mod tests {
         check_equal(
             "    1  2 3 + 4 // *-",
             vec![
-                Token::Number(1),
-                Token::Number(2),
-                Token::Number(3),
+                Token::Number(1.into()),
+                Token::Number(2.into()),
+                Token::Number(3.into()),
                 Token::Operator(Operator::Plus),
-                Token::Number(4),
+                Token::Number(4.into()),
                 Token::Operator(Operator::Slash),
                 Token::Operator(Operator::Slash),
                 Token::Operator(Operator::Star),
@@ -128,4 +364,166 @@ mod tests {
             ],
         )
     }
+
+    #[test]
+    fn test4() {
+        check_equal(
+            "1.5e3-.5 2E+2+1e-1",
+            vec![
+                Token::Number(Literal::new("15", 2).unwrap()),
+                Token::Operator(Operator::Minus),
+                Token::Number(Literal::new("5", -1).unwrap()),
+                Token::Number(Literal::new("2", 2).unwrap()),
+                Token::Operator(Operator::Plus),
+                Token::Number(Literal::new("1", -1).unwrap()),
+            ],
+        )
+    }
+
+    #[test]
+    fn test5() {
+        for input in ["1.2.3", "1e", "2 . 3", "2 $"] {
+            assert!(Tokenizer::tokenize(input.to_string()).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test6() {
+        check_equal(
+            "max(x_1, 2e1,e)",
+            vec![
+                Token::Identifier("max".to_string()),
+                Token::Paren(Paren::Left),
+                Token::Identifier("x_1".to_string()),
+                Token::Comma,
+                Token::Number(Literal::new("2", 1).unwrap()),
+                Token::Comma,
+                Token::Identifier("e".to_string()),
+                Token::Paren(Paren::Right),
+            ],
+        )
+    }
+
+    #[test]
+    fn spans() {
+        let spans = |input: &str| -> Vec<(usize, usize)> {
+            Tokenizer::tokenize_spanned(input)
+                .unwrap()
+                .iter()
+                .map(|(_, span)| (span.start, span.end))
+                .collect()
+        };
+        assert_eq!(
+            vec![(0, 3), (3, 4), (4, 10), (10, 11)],
+            spans("max(1.5e-3)")
+        );
+        assert_eq!(vec![(0, 2), (3, 4), (5, 6)], spans("ab = 1"));
+        assert_eq!(vec![(0, 2), (2, 3), (4, 5)], spans("π= 1"));
+
+        let error = |input: &str| {
+            Tokenizer::tokenize_spanned(input)
+                .unwrap_err()
+                .render(input)
+        };
+        assert_eq!(
+            "Malformed number '1.2.3'\n2 + 1.2.3\n    ^^^^^",
+            error("2 + 1.2.3")
+        );
+        assert_eq!("Unexpected token '$'\n2 $ 3\n  ^", error("2 $ 3"));
+    }
+
+    #[test]
+    fn test7() {
+        check_equal(
+            "rate=0.07",
+            vec![
+                Token::Identifier("rate".to_string()),
+                Token::Equals,
+                Token::Number(Literal::new("7", -2).unwrap()),
+            ],
+        )
+    }
+
+    #[test]
+    fn comparisons() {
+        let op = Token::Operator;
+        let number = |x: u32| Token::Number(x.into());
+        check_equal(
+            "1<=2==!(3>4)!=5<6>=7",
+            vec![
+                number(1),
+                op(Operator::LessEqual),
+                number(2),
+                op(Operator::EqualEqual),
+                op(Operator::Not),
+                Token::Paren(Paren::Left),
+                number(3),
+                op(Operator::Greater),
+                number(4),
+                Token::Paren(Paren::Right),
+                op(Operator::NotEqual),
+                number(5),
+                op(Operator::Less),
+                number(6),
+                op(Operator::GreaterEqual),
+                number(7),
+            ],
+        );
+        check_equal(
+            "a&&b||c ? 1:x= =2",
+            vec![
+                Token::Identifier("a".to_string()),
+                op(Operator::And),
+                Token::Identifier("b".to_string()),
+                op(Operator::Or),
+                Token::Identifier("c".to_string()),
+                Token::Question,
+                number(1),
+                Token::Colon,
+                Token::Identifier("x".to_string()),
+                Token::Equals,
+                Token::Equals,
+                number(2),
+            ],
+        );
+
+        let spans: Vec<_> = Tokenizer::tokenize_spanned("x>=1 && !y")
+            .unwrap()
+            .iter()
+            .map(|(_, span)| (span.start, span.end))
+            .collect();
+        assert_eq!(vec![(0, 1), (1, 3), (3, 4), (5, 7), (8, 9), (9, 10)], spans);
+        let error = Tokenizer::tokenize_spanned("a & b").unwrap_err();
+        assert_eq!("Unexpected token '&'\na & b\n  ^", error.render("a & b"));
+        assert!(Tokenizer::tokenize("a |".to_string()).is_err());
+    }
+
+    #[test]
+    fn streams() {
+        let input = "π = max(1.5e3, é) - 2";
+        // a buffer of one byte splits the chars
+        let read = Tokenizer::read(BufReader::with_capacity(1, input.as_bytes()));
+        assert_eq!(
+            Tokenizer::tokenize_spanned(input).unwrap(),
+            read.collect::<Result<Vec<_>, _>>().unwrap()
+        );
+
+        // the chars after an error are never read
+        let mut read = 0;
+        let chars = "12 + $".chars().chain(std::iter::repeat('1'));
+        let tokens: Vec<_> = Tokenizer::chars(chars.inspect(|_| read += 1)).collect();
+        assert_eq!(3, tokens.len());
+        assert!(tokens[1].is_ok());
+        assert_eq!(
+            Error::UnexpectedChar('$'),
+            tokens[2].clone().unwrap_err().error
+        );
+        assert_eq!(6, read);
+
+        let invalid = Tokenizer::read(&[b'1', b' ', 0xff][..]).last().unwrap();
+        assert_eq!(
+            "Unable to read the input: Invalid UTF-8 at byte 3",
+            invalid.unwrap_err().to_string()
+        );
+    }
 }
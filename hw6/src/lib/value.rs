@@ -0,0 +1,178 @@
+use std::fmt::Display;
+
+use crate::{
+    error::Error,
+    number::Number,
+    token::{Function, Operator},
+};
+
+/// Result of an expression, comparisons and logical operators give booleans
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value<N> {
+    Number(N),
+    Bool(bool),
+}
+
+impl<N: Number> Value<N> {
+    /// The number, else an error that `what` takes numbers, like "Operand of '+'"
+    pub fn into_number(self, what: &str) -> Result<N, Error> {
+        match self {
+            Value::Number(x) => Ok(x),
+            Value::Bool(b) => Err(Error::TypeMismatch(format!(
+                "{} must be a number, got {}",
+                what, b
+            ))),
+        }
+    }
+
+    /// The boolean, else an error that `what` takes booleans
+    pub fn into_bool(self, what: &str) -> Result<bool, Error> {
+        match self {
+            Value::Bool(b) => Ok(b),
+            Value::Number(x) => Err(Error::TypeMismatch(format!(
+                "{} must be a boolean, got {}",
+                what, x
+            ))),
+        }
+    }
+
+    pub fn unary(op: &Operator, x: Self) -> Result<Self, Error> {
+        let what = format!("Operand of '{}'", op);
+        match op {
+            Operator::UnaryMinus => Ok(Value::Number(x.into_number(&what)?.neg()?)),
+            Operator::Not => Ok(Value::Bool(!x.into_bool(&what)?)),
+            _ => Ok(Value::Number(x.into_number(&what)?)),
+        }
+    }
+
+    /// Binary operators, `&&` and `||` without the short circuit.
+    /// '==' and '!=' take two numbers or two booleans
+    pub fn binary(op: &Operator, x1: Self, x2: Self) -> Result<Self, Error> {
+        let what = format!("Operand of '{}'", op);
+        if op.is_logical() {
+            let (b1, b2) = (x1.into_bool(&what)?, x2.into_bool(&what)?);
+            return Ok(Value::Bool(match op {
+                Operator::And => b1 && b2,
+                _ => b1 || b2,
+            }));
+        }
+        if let (Value::Bool(b1), Value::Bool(b2)) = (&x1, &x2) {
+            match op {
+                Operator::EqualEqual => return Ok(Value::Bool(b1 == b2)),
+                Operator::NotEqual => return Ok(Value::Bool(b1 != b2)),
+                _ => {}
+            }
+        }
+        let (x1, x2) = (x1.into_number(&what)?, x2.into_number(&what)?);
+        let b = match op {
+            Operator::Plus => return x1.add(x2).map(Value::Number),
+            Operator::Minus => return x1.sub(x2).map(Value::Number),
+            Operator::Star => return x1.mul(x2).map(Value::Number),
+            Operator::Slash => return x1.div(x2).map(Value::Number),
+            Operator::Percent => return x1.rem(x2).map(Value::Number),
+            Operator::Caret => return x1.pow(x2).map(Value::Number),
+            Operator::Less => x1 < x2,
+            Operator::LessEqual => x1 <= x2,
+            Operator::Greater => x1 > x2,
+            Operator::GreaterEqual => x1 >= x2,
+            Operator::EqualEqual => x1 == x2,
+            Operator::NotEqual => x1 != x2,
+            _ => unreachable!("'{}' is not binary", op),
+        };
+        Ok(Value::Bool(b))
+    }
+
+    /// Value of `&&` or `||` if its left operand `x` decides it, `None` if the right one is needed
+    pub fn short_circuit(op: &Operator, x: Self) -> Result<Option<Self>, Error> {
+        let b = x.into_bool(&format!("Operand of '{}'", op))?;
+        Ok(match op {
+            Operator::And if !b => Some(Value::Bool(false)),
+            Operator::Or if b => Some(Value::Bool(true)),
+            _ => None,
+        })
+    }
+
+    /// Built-in functions take numbers
+    pub fn call(function: Function, args: Vec<Self>) -> Result<Self, Error> {
+        let what = format!("Argument of '{}'", function.name());
+        let args = args
+            .into_iter()
+            .map(|x| x.into_number(&what))
+            .collect::<Result<_, _>>()?;
+        N::call(function, args).map(Value::Number)
+    }
+}
+
+impl<N> From<N> for Value<N> {
+    fn from(x: N) -> Self {
+        Value::Number(x)
+    }
+}
+
+impl<N: Display> Display for Value<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Number(x) => write!(f, "{}", x),
+            Value::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Value;
+    use crate::{
+        error::Error,
+        token::{Function, Operator},
+    };
+
+    #[test]
+    fn types() {
+        let binary = |op, x1, x2| Value::<i32>::binary(&op, x1, x2);
+        let (one, two) = (Value::Number(1), Value::Number(2));
+        assert_eq!(
+            Ok(Value::Bool(true)),
+            binary(Operator::Less, one.clone(), two.clone())
+        );
+        assert_eq!(
+            Ok(Value::Bool(false)),
+            binary(Operator::EqualEqual, Value::Bool(true), Value::Bool(false))
+        );
+        assert_eq!(
+            Err(Error::TypeMismatch(
+                "Operand of '+' must be a number, got true".to_string()
+            )),
+            binary(Operator::Plus, one.clone(), Value::Bool(true))
+        );
+        assert_eq!(
+            Err(Error::TypeMismatch(
+                "Operand of '&&' must be a boolean, got 2".to_string()
+            )),
+            binary(Operator::And, Value::Bool(true), two.clone())
+        );
+        assert!(binary(Operator::NotEqual, one.clone(), Value::Bool(true)).is_err());
+        assert!(Value::unary(&Operator::Not, one.clone()).is_err());
+        assert_eq!(
+            Ok(one.clone()),
+            Value::unary(&Operator::UnaryPlus, one.clone())
+        );
+        assert_eq!(
+            Err(Error::TypeMismatch(
+                "Operand of '+' must be a number, got true".to_string()
+            )),
+            Value::<i32>::unary(&Operator::UnaryPlus, Value::Bool(true))
+        );
+        assert!(Value::<i32>::unary(&Operator::UnaryMinus, Value::Bool(true)).is_err());
+        assert!(Value::call(Function::Max, vec![one, Value::Bool(false)]).is_err());
+    }
+
+    #[test]
+    fn short_circuit() {
+        let short = |op, b| Value::<i32>::short_circuit(&op, Value::Bool(b));
+        assert_eq!(Ok(Some(Value::Bool(false))), short(Operator::And, false));
+        assert_eq!(Ok(None), short(Operator::And, true));
+        assert_eq!(Ok(Some(Value::Bool(true))), short(Operator::Or, true));
+        assert_eq!(Ok(None), short(Operator::Or, false));
+        assert!(Value::<i32>::short_circuit(&Operator::Or, Value::Number(0)).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use crate::{
+    error::Error,
+    expr::Expr,
+    token::{Function, Literal, Operator, TokenListVisitor},
+};
+
+/// Builds the expression tree of RPN
+pub struct AstVisitor {
+    stack: Vec<Expr>,
+}
+
+impl AstVisitor {
+    pub fn new() -> Self {
+        AstVisitor { stack: vec![] }
+    }
+
+    fn pop(&mut self, missing: impl FnOnce() -> String) -> Result<Expr, Error> {
+        self.stack.pop().ok_or_else(|| Error::invalid(&missing()))
+    }
+}
+
+impl Default for AstVisitor {
+    fn default() -> Self {
+        AstVisitor::new()
+    }
+}
+
+impl TokenListVisitor for AstVisitor {
+    type Error = Error;
+    type Output = Expr;
+
+    fn visit_left(&mut self) -> Result<(), Self::Error> {
+        Err(Error::invalid("No parenthesis in RPN expected"))
+    }
+
+    fn visit_right(&mut self) -> Result<(), Self::Error> {
+        Err(Error::invalid("No parenthesis in RPN expected"))
+    }
+
+    /// Negative literals are unary minus of a number, like they were written
+    fn visit_number(&mut self, x: Literal) -> Result<(), Self::Error> {
+        let negative = x.is_negative();
+        self.stack.push(Expr::Number(x.abs()));
+        if negative {
+            self.visit_op(Operator::UnaryMinus)?;
+        }
+        Ok(())
+    }
+
+    fn visit_op(&mut self, op: Operator) -> Result<(), Self::Error> {
+        let missing = || format!("Missing operand of '{}'", op);
+        let x2 = self.pop(missing)?;
+        let expr = match op.arity() {
+            1 => Expr::Unary(op, Box::new(x2)),
+            2 => {
+                let x1 = self.pop(missing)?;
+                Expr::Binary(op, Box::new(x1), Box::new(x2))
+            }
+            _ => {
+                let x1 = self.pop(missing)?;
+                let c = self.pop(missing)?;
+                Expr::Conditional(Box::new(c), Box::new(x1), Box::new(x2))
+            }
+        };
+        self.stack.push(expr);
+        Ok(())
+    }
+
+    fn visit_comma(&mut self) -> Result<(), Self::Error> {
+        Err(Error::invalid("No commas in RPN expected"))
+    }
+
+    fn visit_identifier(&mut self, name: String) -> Result<(), Self::Error> {
+        self.stack.push(Expr::Variable(name));
+        Ok(())
+    }
+
+    fn visit_function(&mut self, function: Function, args: usize) -> Result<(), Self::Error> {
+        function.check_arity(args)?;
+        if self.stack.len() < args {
+            return Err(Error::invalid(&format!(
+                "Missing argument of '{}'",
+                function.name()
+            )));
+        }
+        let args = self.stack.split_off(self.stack.len() - args);
+        self.stack.push(Expr::Call(function, args));
+        Ok(())
+    }
+
+    fn visit_equals(&mut self) -> Result<(), Self::Error> {
+        Err(Error::invalid("No '=' in RPN expected"))
+    }
+
+    fn visit_assign(&mut self, name: String) -> Result<(), Self::Error> {
+        let x = self.pop(|| format!("Missing value of '{}'", name))?;
+        self.stack.push(Expr::Assign(name, Box::new(x)));
+        Ok(())
+    }
+
+    /// The markers of short circuits and conditionals don't change the tree
+    fn visit_question(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn visit_colon(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn visit_short_circuit(&mut self, _: Operator) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn get_result(mut self) -> Result<Self::Output, Self::Error> {
+        let res = self.pop(|| "Unexpected empty expression stack".to_string())?;
+        if !self.stack.is_empty() {
+            return Err(Error::invalid(
+                "Unexpected elements left in expression stack",
+            ));
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::AstVisitor;
+    use crate::{
+        expr::Expr, token::TokenListVisitor, tokenizer::Tokenizer,
+        visitors::parser_visitor::ParserVisitor,
+    };
+
+    pub fn tree(input: &str) -> Expr {
+        let tokens = Tokenizer::tokenize(input.to_string()).unwrap();
+        let rpn = ParserVisitor::visit_tokens(tokens).unwrap();
+        AstVisitor::visit_tokens(rpn).unwrap()
+    }
+
+    fn check_infix(input: &str, expected: &str) {
+        let expr = tree(input);
+        assert_eq!(expected, expr.infix());
+        // printed expression parses back to the same tree
+        assert_eq!(expr, tree(expected));
+    }
+
+    #[test]
+    fn infix() {
+        check_infix("1 + 2 * 3", "1 + 2 * 3");
+        check_infix("(1 + 2) * 3", "(1 + 2) * 3");
+        check_infix("((1)) + ((2 * 3))", "1 + 2 * 3");
+        check_infix("1 - (2 - 3)", "1 - (2 - 3)");
+        check_infix("(1 - 2) - 3", "1 - 2 - 3");
+        check_infix("2 ^ (3 ^ 2)", "2 ^ 3 ^ 2");
+        check_infix("(2 ^ 3) ^ 2", "(2 ^ 3) ^ 2");
+        check_infix("8 / (4 * 2)", "8 / (4 * 2)");
+    }
+
+    #[test]
+    fn unary() {
+        check_infix("-(2 ^ 2)", "-2 ^ 2");
+        check_infix("(-2) ^ 2", "(-2) ^ 2");
+        check_infix("-(1 + 2)", "-(1 + 2)");
+        check_infix("2 ^ (-3)", "2 ^ -3");
+        check_infix("2 * (-3) + 1", "2 * -3 + 1");
+        check_infix("- (-2)", "--2");
+    }
+
+    #[test]
+    fn calls_and_assignment() {
+        check_infix("max((1 + 2), (3))", "max(1 + 2, 3)");
+        check_infix("x = (log(2, y) + 1) * 2", "x = (log(2, y) + 1) * 2");
+    }
+
+    #[test]
+    fn logic() {
+        check_infix("(a < b) == (c >= 1)", "a < b == c >= 1");
+        check_infix("!(a && b) || !c", "!(a && b) || !c");
+        check_infix("a && (b || c)", "a && (b || c)");
+        check_infix("(a == b) != c", "a == b != c");
+        check_infix("a ? b : (c ? d : e)", "a ? b : c ? d : e");
+        check_infix("(a ? b : c) ? d : e", "(a ? b : c) ? d : e");
+        check_infix("a ? (b ? c : d) : e", "a ? (b ? c : d) : e");
+        check_infix("x = (a > 0 ? 1 : 2) * 3", "x = (a > 0 ? 1 : 2) * 3");
+        assert_eq!("?: > a 0 neg a a", tree("a > 0 ? -a : a").prefix());
+    }
+
+    #[test]
+    fn prefix() {
+        assert_eq!("+ 1 * 2 3", tree("1 + 2 * 3").prefix());
+        assert_eq!("* + 1 2 3", tree("(1 + 2) * 3").prefix());
+        assert_eq!("neg ^ 2 2", tree("-2 ^ 2").prefix());
+        assert_eq!(
+            "= x max:3 1 abs y 3",
+            tree("x = max(1, abs(y), 3)").prefix()
+        );
+    }
+
+    #[test]
+    fn tree_lines() {
+        assert_eq!(
+            "= x\n  *\n    neg\n      y\n    max:2\n      1\n      2",
+            tree("x = -y * max(1, 2)").tree()
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let rpn = Tokenizer::tokenize("1 2".to_string()).unwrap();
+        assert!(AstVisitor::visit_tokens(rpn).is_err());
+    }
+}
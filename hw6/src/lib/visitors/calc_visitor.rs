@@ -1,56 +1,242 @@
-use crate::token::{Operator, TokenListVisitor};
+use std::{fmt::Display, marker::PhantomData};
 
-pub struct CalcVisitor {
-    stack: Vec<i32>,
+use crate::{
+    environment::Environment,
+    error::Error,
+    number::Number,
+    span::{Span, SpanError},
+    token::{Function, Literal, Operator, Token, TokenListVisitor},
+    value::Value,
+};
+
+/// Computes RPN with numbers of type `N`.
+/// `&&`, `||` and `?:` only end the operands their markers started,
+/// see `Token::ShortCircuit` and `Token::Question`
+pub struct CalcVisitor<'e, N: Number = i32> {
+    stack: Vec<Value<N>>,
+    number: PhantomData<N>,
+    /// Names are resolved against it, `None` has no names
+    environment: Option<&'e Environment<N>>,
+    /// Variable the result is assigned to, it's left to the owner of the environment
+    target: Option<String>,
+    /// Operand or branch whose tokens are passed over
+    skip: Option<Skip>,
+}
+
+/// Tokens skipped by a short circuit or a branch not taken
+struct Skip {
+    /// Short circuits and conditionals started in the skipped tokens
+    depth: usize,
+    /// Whether ':' ends the skipped tokens, else `&&`, `||` or `?:` does
+    to_colon: bool,
 }
 
-impl TokenListVisitor for CalcVisitor {
-    type Error = &'static str;
-    type Output = i32;
+impl<'e, N: Number> CalcVisitor<'e, N> {
+    pub fn new() -> Self {
+        CalcVisitor {
+            stack: vec![],
+            number: PhantomData,
+            environment: None,
+            target: None,
+            skip: None,
+        }
+    }
+
+    /// Calculator that resolves names against `environment`, it isn't copied
+    pub fn with_environment(environment: &'e Environment<N>) -> Self {
+        CalcVisitor {
+            environment: Some(environment),
+            ..Self::new()
+        }
+    }
+
+    /// Computes RPN `tokens`, returns the result and the variable it's assigned to.
+    /// Errors of the whole expression span all of it
+    pub fn run(
+        mut self,
+        tokens: Vec<(Token, Span)>,
+    ) -> Result<(Value<N>, Option<String>), SpanError> {
+        let all = tokens
+            .iter()
+            .map(|(_, span)| *span)
+            .reduce(|a, b| Span::new(a.start.min(b.start), a.end.max(b.end)))
+            .unwrap_or_default();
+        for (token, span) in tokens {
+            self.visit_at(token, span)?;
+        }
+        let target = self.target.take();
+        let x = self.get_result().map_err(|e| e.or_at(all))?;
+        Ok((x, target))
+    }
 
-    fn new() -> Self {
-        CalcVisitor { stack: vec![] }
+    fn pop(&mut self, what: impl Display) -> Result<Value<N>, Error> {
+        self.stack
+            .pop()
+            .ok_or_else(|| Error::invalid(&format!("Missing operand of '{}'", what)))
     }
 
+    fn skip_to(&mut self, to_colon: bool) {
+        self.skip = Some(Skip { depth: 0, to_colon });
+    }
+}
+
+impl<N: Number> Default for CalcVisitor<'_, N> {
+    fn default() -> Self {
+        CalcVisitor::new()
+    }
+}
+
+impl<N: Number> TokenListVisitor for CalcVisitor<'_, N> {
+    type Error = SpanError;
+    type Output = Value<N>;
+
     fn visit_left(&mut self) -> Result<(), Self::Error> {
-        Err("No parenthesis in RPN expected")
+        Err(Error::invalid("No parenthesis in RPN expected").into())
     }
 
     fn visit_right(&mut self) -> Result<(), Self::Error> {
-        Err("No parenthesis in RPN expected")
+        Err(Error::invalid("No parenthesis in RPN expected").into())
     }
 
-    fn visit_number(&mut self, x: u32) -> Result<(), Self::Error> {
-        self.stack.push(x as i32);
+    fn visit_number(&mut self, x: Literal) -> Result<(), Self::Error> {
+        if self.skip.is_none() {
+            self.stack.push(Value::Number(N::from_literal(&x)?));
+        }
         Ok(())
     }
 
-    fn visit_op(&mut self, op: crate::token::Operator) -> Result<(), Self::Error> {
-        let x2 = self.stack.pop().ok_or("expected argument")?;
-        let x1 = self.stack.pop().ok_or("expected argument")?;
+    /// `&&` and `||` check the right operand, `?:` ends the second branch
+    fn visit_op(&mut self, op: Operator) -> Result<(), Self::Error> {
+        let ends = matches!(op, Operator::And | Operator::Or | Operator::Conditional);
+        if let Some(skip) = &mut self.skip {
+            match (ends, skip.depth, skip.to_colon) {
+                (false, _, _) => {}
+                (true, 0, false) => self.skip = None,
+                (true, 0, true) => return Err(Error::invalid("Missing ':' of '?'").into()),
+                (true, _, _) => skip.depth -= 1,
+            }
+            return Ok(());
+        }
+        let x2 = self.pop(&op)?;
         let res = match op {
-            Operator::Plus => Ok(x1 + x2),
-            Operator::Minus => Ok(x1 - x2),
-            Operator::Star => Ok(x1 * x2),
-            Operator::Slash => {
-                if x2 != 0 {
-                    Ok(x1 / x2)
-                } else {
-                    Err("Division by zero")
-                }
+            Operator::And | Operator::Or => {
+                Value::Bool(x2.into_bool(&format!("Operand of '{}'", op))?)
+            }
+            Operator::Conditional => x2,
+            _ if op.arity() == 1 => Value::unary(&op, x2)?,
+            _ => {
+                let x1 = self.pop(&op)?;
+                Value::binary(&op, x1, x2)?
             }
-        }?;
+        };
         self.stack.push(res);
         Ok(())
     }
 
+    fn visit_comma(&mut self) -> Result<(), Self::Error> {
+        Err(Error::invalid("No commas in RPN expected").into())
+    }
+
+    fn visit_identifier(&mut self, name: String) -> Result<(), Self::Error> {
+        if self.skip.is_some() {
+            return Ok(());
+        }
+        let x = match self.environment {
+            Some(environment) => environment.lookup(&name)?,
+            None => return Err(Error::UnknownVariable(name).into()),
+        };
+        self.stack.push(x.clone());
+        Ok(())
+    }
+
+    fn visit_function(&mut self, function: Function, args: usize) -> Result<(), Self::Error> {
+        if self.skip.is_some() {
+            return Ok(());
+        }
+        function.check_arity(args)?;
+        if self.stack.len() < args {
+            return Err(
+                Error::invalid(&format!("Missing argument of '{}'", function.name())).into(),
+            );
+        }
+        let args = self.stack.split_off(self.stack.len() - args);
+        self.stack.push(Value::call(function, args)?);
+        Ok(())
+    }
+
+    fn visit_equals(&mut self) -> Result<(), Self::Error> {
+        Err(Error::invalid("No '=' in RPN expected").into())
+    }
+
+    /// The value stays on the stack, it's the result of the assignment
+    fn visit_assign(&mut self, name: String) -> Result<(), Self::Error> {
+        if self.skip.is_some() {
+            return Ok(());
+        }
+        if self.stack.is_empty() {
+            return Err(Error::invalid(&format!("Missing value of '{}'", name)).into());
+        }
+        if let Some(environment) = self.environment {
+            environment.check_assign(&name)?;
+        }
+        self.target = Some(name);
+        Ok(())
+    }
+
+    /// The first branch is skipped if the condition is false
+    fn visit_question(&mut self) -> Result<(), Self::Error> {
+        if let Some(skip) = &mut self.skip {
+            skip.depth += 1;
+            return Ok(());
+        }
+        if !self.pop('?')?.into_bool("Condition of '?'")? {
+            self.skip_to(true);
+        }
+        Ok(())
+    }
+
+    /// The first branch is done, so the second one is skipped
+    fn visit_colon(&mut self) -> Result<(), Self::Error> {
+        match &self.skip {
+            Some(Skip {
+                depth: 0,
+                to_colon: true,
+            }) => self.skip = None,
+            Some(_) => {}
+            None => self.skip_to(false),
+        }
+        Ok(())
+    }
+
+    /// The left operand stays as the result if it decides it
+    fn visit_short_circuit(&mut self, op: Operator) -> Result<(), Self::Error> {
+        if let Some(skip) = &mut self.skip {
+            skip.depth += 1;
+            return Ok(());
+        }
+        let x = self.pop(&op)?;
+        if let Some(res) = Value::short_circuit(&op, x)? {
+            self.stack.push(res);
+            self.skip_to(false);
+        }
+        Ok(())
+    }
+
+    /// Errors are put at the token they happen at
+    fn visit_at(&mut self, token: Token, span: Span) -> Result<(), Self::Error> {
+        self.visit(token).map_err(|e| e.or_at(span))
+    }
+
     fn get_result(mut self) -> Result<Self::Output, Self::Error> {
+        if self.skip.is_some() {
+            return Err(Error::invalid("Unexpected end of a skipped operand").into());
+        }
         let res = self
             .stack
             .pop()
-            .ok_or("Unexpected empty evaluation stack")?;
+            .ok_or_else(|| Error::invalid("Unexpected empty evaluation stack"))?;
         if !self.stack.is_empty() {
-            return Err("Unexpected elements left in evaluation stack");
+            return Err(Error::invalid("Unexpected elements left in evaluation stack").into());
         }
         Ok(res)
     }
@@ -58,16 +244,181 @@ impl TokenListVisitor for CalcVisitor {
 
 #[cfg(test)]
 mod test {
+    use num_rational::BigRational;
+
     use super::CalcVisitor;
-    use crate::visitors::parser_visitor::test::check_equal;
+    use crate::{
+        environment::Environment,
+        error::Error,
+        number::Number,
+        span::SpanError,
+        token::TokenListVisitor,
+        tokenizer::Tokenizer,
+        value::Value,
+        visitors::parser_visitor::{test::check_equal, ParserVisitor},
+    };
 
     #[test]
     fn test1() {
-        check_equal::<CalcVisitor>("2 3 +", 5)
+        check_equal::<CalcVisitor>("2 3 +", Value::Number(5))
     }
 
     #[test]
     fn test2() {
-        check_equal::<CalcVisitor>("3 4 - 5 2 / +", 1)
+        check_equal::<CalcVisitor>("3 4 - 5 2 / +", Value::Number(1))
+    }
+
+    #[test]
+    fn test3() {
+        check_equal::<CalcVisitor<BigRational>>(
+            "7 2 / 0.5 +",
+            Value::Number(BigRational::from_integer(4.into())),
+        )
+    }
+
+    fn value<N: Number>(input: &str) -> Result<Value<N>, SpanError> {
+        let tokens = Tokenizer::tokenize_spanned(input)?;
+        let rpn = ParserVisitor::parse(tokens)?;
+        CalcVisitor::<N>::visit_spanned(rpn)
+    }
+
+    fn calc<N: Number>(input: &str) -> Result<N, SpanError> {
+        Ok(value::<N>(input)?.into_number("Result")?)
+    }
+
+    #[test]
+    fn test4() {
+        assert_eq!(Ok(1), calc::<i32>("-3 + 4"));
+        assert_eq!(Ok(-4), calc::<i32>("-2 ^ 2"));
+        assert_eq!(Ok(512), calc::<i32>("2 ^ 3 ^ 2"));
+        assert_eq!(Ok(-1), calc::<i32>("-7 % 3"));
+        assert_eq!(Ok(2), calc::<i32>("- -2"));
+        assert!(calc::<i32>("2 ^ -1").is_err());
+        assert_eq!(Ok(0.25), calc::<f64>("2 ^ -2"));
+        assert!(calc::<f64>("-8 ^ 0.5").unwrap() < 0.0);
+        assert!(f64::pow(-8.0, 0.5).is_err());
+        assert_eq!(
+            Ok(BigRational::new(1.into(), 8.into())),
+            calc::<BigRational>("2 ^ -3")
+        );
+        assert!(calc::<BigRational>("4 ^ 0.5").is_err());
+        assert!(calc::<BigRational>("0 ^ -1").is_err());
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(Ok(11), calc::<i32>("max(3, 7, -2) + min(4)"));
+        assert_eq!(Ok(5), calc::<i32>("abs(2 - 7) * sqrt(1)"));
+        assert_eq!(Ok(3), calc::<i32>("log(2, 8)"));
+        assert_eq!(Ok(118), calc::<i32>("round(115, -1) - round(5 / 2)"));
+        assert!(calc::<i32>("sqrt(2)").is_err());
+        assert!(calc::<i32>("abs(-2147483648)").is_err());
+
+        assert_eq!(Ok(1.41), calc::<f64>("round(sqrt(2), 2)"));
+        assert_eq!(Ok(1.0), calc::<f64>("cos(0) + sin(0) + ln(1)"));
+        assert_eq!(Ok(2.0), calc::<f64>("round(log(10, 100))"));
+        assert!(calc::<f64>("sqrt(-1)").is_err());
+        assert!(calc::<f64>("log(1, 5)").is_err());
+
+        let ratio = |a: i32, b: i32| BigRational::new(a.into(), b.into());
+        assert_eq!(Ok(ratio(2, 3)), calc::<BigRational>("sqrt(4 / 9)"));
+        assert_eq!(Ok(ratio(-2, 1)), calc::<BigRational>("log(2, 0.25)"));
+        assert_eq!(Ok(ratio(-3, 1)), calc::<BigRational>("round(-2.5)"));
+        assert_eq!(Ok(ratio(13, 10)), calc::<BigRational>("round(1.25, 1)"));
+        assert!(calc::<BigRational>("sqrt(2)").is_err());
+        assert!(calc::<BigRational>("sin(1)").is_err());
+    }
+
+    #[test]
+    fn error_spans() {
+        let error = |input: &str| calc::<i32>(input).unwrap_err().render(input);
+        assert_eq!(
+            "Division by zero\n1 + 2 / (1 - 1)\n      ^",
+            error("1 + 2 / (1 - 1)")
+        );
+        assert_eq!(
+            "Overflow in abs(-2147483648)\n1 + abs(-2147483648)\n    ^^^^^^^^^^^^^^^^",
+            error("1 + abs(-2147483648)")
+        );
+        assert_eq!("Unknown variable 'x'\n2 * x\n    ^", error("2 * x"));
+        assert_eq!(
+            "Literal -2147483649 is out of range of i32\n1 - -2147483649\n    ^^^^^^^^^^^",
+            error("1 - -2147483649")
+        );
+    }
+
+    #[test]
+    fn logic() {
+        let bool = |input| value::<i32>(input).map(|x| x.into_bool("Result").unwrap());
+        assert_eq!(Ok(true), bool("1 + 1 == 2 && 3 >= 3"));
+        assert_eq!(Ok(true), bool("1 < 2 == 2 < 3"));
+        assert_eq!(Ok(false), bool("!(1 < 2) || 1 != 1"));
+        assert_eq!(Ok(true), bool("1 > 2 || 2 > 1 && !(3 <= 2)"));
+        assert_eq!(Ok(5), calc::<i32>("1 < 2 ? 5 : 6"));
+        assert_eq!(Ok(-1), calc::<i32>("-2 > 0 ? 1 : -2 < 0 ? -1 : 0"));
+        assert_eq!(Ok(4), calc::<i32>("(1 > 2 ? 3 : 2) * 2"));
+        assert_eq!(Ok(30), calc::<i32>("2 > 1 ? 3 > 4 ? 10 : 30 : 50"));
+        assert_eq!(Ok(Value::Bool(true)), value::<f64>("0.1 + 0.2 > 0.3"));
+
+        let error = |input: &str| value::<i32>(input).unwrap_err().render(input);
+        assert_eq!(
+            "Operand of '&&' must be a boolean, got 1\n1 && 2 > 1\n  ^^",
+            error("1 && 2 > 1")
+        );
+        assert_eq!(
+            "Operand of '+' must be a number, got true\n(1 < 2) + 1\n        ^",
+            error("(1 < 2) + 1")
+        );
+        assert_eq!(
+            "Condition of '?' must be a boolean, got 3\n3 ? 1 : 2\n  ^",
+            error("3 ? 1 : 2")
+        );
+    }
+
+    #[test]
+    fn short_circuit() {
+        // the skipped operands would fail
+        let bool = |input| value::<i32>(input).map(|x| x.into_bool("Result").unwrap());
+        assert_eq!(Ok(false), bool("1 > 2 && 1 / 0 > 0"));
+        assert_eq!(Ok(true), bool("2 > 1 || x"));
+        assert_eq!(Ok(true), bool("1 < 2 || y > 0 && z"));
+        assert_eq!(Ok(7), calc::<i32>("1 < 2 ? 7 : 1 / 0"));
+        assert_eq!(Ok(8), calc::<i32>("1 > 2 ? (1 < 0 ? x : y) : max(8, 1)"));
+        assert_eq!(Ok(9), calc::<i32>("1 > 2 && u ? v : 9"));
+        // what is computed still fails
+        assert!(bool("1 < 2 && 1 / 0 > 0").is_err());
+        assert!(calc::<i32>("1 > 2 ? 7 : 1 / 0").is_err());
+        assert!(bool("1 < 2 && 3").is_err());
+    }
+
+    #[test]
+    fn assign() {
+        // the environment is only read, the owner assigns
+        let environment = Environment::<i32>::with_constants();
+        let rpn = ParserVisitor::parse(Tokenizer::tokenize_spanned("x = 2 * 3").unwrap());
+        assert_eq!(
+            Ok((Value::Number(6), Some("x".to_string()))),
+            CalcVisitor::with_environment(&environment).run(rpn.unwrap())
+        );
+        assert_eq!(None, environment.get("x"));
+    }
+
+    #[test]
+    fn streaming() {
+        // "1 + 1 + ... + 1" read as it's computed
+        let n = 100_000;
+        let text = std::iter::repeat_n("1 + ", n).chain(["1"]);
+        let tokens = Tokenizer::chars(text.flat_map(str::chars));
+        let rpn = ParserVisitor::stream(tokens);
+        assert_eq!(
+            Ok(Value::Number(n as i32 + 1)),
+            CalcVisitor::try_visit_spanned(rpn)
+        );
+
+        // an endless input stops at an error
+        let text = "2 * (3 $ ".chars().chain(std::iter::repeat('1'));
+        let rpn = ParserVisitor::stream(Tokenizer::chars(text));
+        let error = CalcVisitor::<i32>::try_visit_spanned(rpn).unwrap_err();
+        assert_eq!(Error::UnexpectedChar('$'), error.error);
     }
 }
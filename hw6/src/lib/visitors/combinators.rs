@@ -0,0 +1,267 @@
+use crate::{
+    span::{Span, SpanError},
+    token::{Function, Literal, Operator, Paren, Token, TokenListVisitor},
+};
+
+/// Methods of single tokens for visitors that override `visit` and `visit_at`
+macro_rules! visit_by_token {
+    () => {
+        fn visit_left(&mut self) -> Result<(), Self::Error> {
+            self.visit(Token::Paren(Paren::Left))
+        }
+
+        fn visit_right(&mut self) -> Result<(), Self::Error> {
+            self.visit(Token::Paren(Paren::Right))
+        }
+
+        fn visit_number(&mut self, x: Literal) -> Result<(), Self::Error> {
+            self.visit(Token::Number(x))
+        }
+
+        fn visit_op(&mut self, op: Operator) -> Result<(), Self::Error> {
+            self.visit(Token::Operator(op))
+        }
+
+        fn visit_comma(&mut self) -> Result<(), Self::Error> {
+            self.visit(Token::Comma)
+        }
+
+        fn visit_identifier(&mut self, name: String) -> Result<(), Self::Error> {
+            self.visit(Token::Identifier(name))
+        }
+
+        fn visit_function(&mut self, function: Function, args: usize) -> Result<(), Self::Error> {
+            self.visit(Token::Function(function, args))
+        }
+
+        fn visit_equals(&mut self) -> Result<(), Self::Error> {
+            self.visit(Token::Equals)
+        }
+
+        fn visit_assign(&mut self, name: String) -> Result<(), Self::Error> {
+            self.visit(Token::Assign(name))
+        }
+
+        fn visit_question(&mut self) -> Result<(), Self::Error> {
+            self.visit(Token::Question)
+        }
+
+        fn visit_colon(&mut self) -> Result<(), Self::Error> {
+            self.visit(Token::Colon)
+        }
+
+        fn visit_short_circuit(&mut self, op: Operator) -> Result<(), Self::Error> {
+            self.visit(Token::ShortCircuit(op))
+        }
+    };
+}
+
+/// Gives every token to both visitors in one pass, the results come in a pair.
+/// Errors of the second visitor are converted to the errors of the first
+pub struct Pair<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> Pair<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Pair { first, second }
+    }
+}
+
+impl<A: Default, B: Default> Default for Pair<A, B> {
+    fn default() -> Self {
+        Pair::new(A::default(), B::default())
+    }
+}
+
+impl<A, B> TokenListVisitor for Pair<A, B>
+where
+    A: TokenListVisitor,
+    B: TokenListVisitor,
+    B::Error: Into<A::Error>,
+{
+    type Output = (A::Output, B::Output);
+    type Error = A::Error;
+
+    visit_by_token!();
+
+    fn visit(&mut self, token: Token) -> Result<(), Self::Error> {
+        self.first.visit(token.clone())?;
+        self.second.visit(token).map_err(Into::into)
+    }
+
+    fn visit_at(&mut self, token: Token, span: Span) -> Result<(), Self::Error> {
+        self.first.visit_at(token.clone(), span)?;
+        self.second.visit_at(token, span).map_err(Into::into)
+    }
+
+    fn get_result(self) -> Result<Self::Output, Self::Error> {
+        let first = self.first.get_result()?;
+        let second = self.second.get_result().map_err(Into::into)?;
+        Ok((first, second))
+    }
+}
+
+/// Visitor with its result changed by a function
+pub struct Map<V, F> {
+    visitor: V,
+    f: F,
+}
+
+impl<V, F> Map<V, F> {
+    pub fn new(visitor: V, f: F) -> Self {
+        Map { visitor, f }
+    }
+}
+
+impl<V, F, O> TokenListVisitor for Map<V, F>
+where
+    V: TokenListVisitor,
+    F: FnOnce(V::Output) -> O,
+{
+    type Output = O;
+    type Error = V::Error;
+
+    visit_by_token!();
+
+    fn visit(&mut self, token: Token) -> Result<(), Self::Error> {
+        self.visitor.visit(token)
+    }
+
+    fn visit_at(&mut self, token: Token, span: Span) -> Result<(), Self::Error> {
+        self.visitor.visit_at(token, span)
+    }
+
+    fn get_result(self) -> Result<Self::Output, Self::Error> {
+        self.visitor.get_result().map(self.f)
+    }
+}
+
+/// Visitor that shows every token to a function before visiting it
+pub struct Inspect<V, F> {
+    visitor: V,
+    f: F,
+}
+
+impl<V, F> Inspect<V, F> {
+    pub fn new(visitor: V, f: F) -> Self {
+        Inspect { visitor, f }
+    }
+}
+
+impl<V, F> TokenListVisitor for Inspect<V, F>
+where
+    V: TokenListVisitor,
+    F: FnMut(&Token),
+{
+    type Output = V::Output;
+    type Error = V::Error;
+
+    visit_by_token!();
+
+    fn visit(&mut self, token: Token) -> Result<(), Self::Error> {
+        (self.f)(&token);
+        self.visitor.visit(token)
+    }
+
+    fn visit_at(&mut self, token: Token, span: Span) -> Result<(), Self::Error> {
+        (self.f)(&token);
+        self.visitor.visit_at(token, span)
+    }
+
+    fn get_result(self) -> Result<Self::Output, Self::Error> {
+        self.visitor.get_result()
+    }
+}
+
+/// Visitor fed through `dyn`, its result stays in it, see `Sink`
+pub trait DynVisitor {
+    fn visit_dyn(&mut self, token: Token, span: Span) -> Result<(), SpanError>;
+}
+
+impl<V> DynVisitor for V
+where
+    V: TokenListVisitor,
+    V::Error: Into<SpanError>,
+{
+    fn visit_dyn(&mut self, token: Token, span: Span) -> Result<(), SpanError> {
+        self.visit_at(token, span).map_err(Into::into)
+    }
+}
+
+/// Visitor that feeds a borrowed `dyn` one, the owner takes its result after
+pub struct Sink<'a>(pub &'a mut dyn DynVisitor);
+
+impl TokenListVisitor for Sink<'_> {
+    type Output = ();
+    type Error = SpanError;
+
+    visit_by_token!();
+
+    fn visit(&mut self, token: Token) -> Result<(), Self::Error> {
+        self.0.visit_dyn(token, Span::default())
+    }
+
+    fn visit_at(&mut self, token: Token, span: Span) -> Result<(), Self::Error> {
+        self.0.visit_dyn(token, span)
+    }
+
+    fn get_result(self) -> Result<Self::Output, Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        error::Error,
+        expr::Expr,
+        span::{Span, SpanError},
+        token::{Token, TokenListVisitor},
+        tokenizer::Tokenizer,
+        value::Value,
+        visitors::{
+            ast_visitor::AstVisitor, calc_visitor::CalcVisitor, parser_visitor::ParserVisitor,
+        },
+    };
+
+    fn rpn(input: &str) -> Vec<(Token, Span)> {
+        ParserVisitor::parse(Tokenizer::tokenize_spanned(input).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn pair() {
+        let (x, tree) = CalcVisitor::<i32>::new()
+            .pair(AstVisitor::new())
+            .run_tokens(rpn("2 * (3 + 4)").into_iter().map(|(token, _)| token))
+            .unwrap();
+        assert_eq!("2 * (3 + 4)", tree.infix());
+        assert_eq!(Value::Number(14), x);
+
+        // both visitors see the spans
+        let error = CalcVisitor::<i32>::new()
+            .pair(AstVisitor::new())
+            .run_spanned(rpn("1 + 2 / 0"))
+            .unwrap_err();
+        assert_eq!(
+            SpanError::new(Error::DivisionByZero, Span::new(6, 7)),
+            error
+        );
+    }
+
+    #[test]
+    fn map_and_inspect() {
+        let mut seen = 0;
+        let infix = AstVisitor::new()
+            .inspect(|_| seen += 1)
+            .map(|tree: Expr| tree.infix())
+            .run_tokens(rpn("max(1, (2))").into_iter().map(|(token, _)| token));
+        assert_eq!(Ok("max(1, 2)".to_string()), infix);
+        assert_eq!(3, seen);
+
+        let visitor = || CalcVisitor::<i32>::new().map(|x| x.to_string());
+        assert_eq!(Ok("3".to_string()), visitor().run_spanned(rpn("1 + 2")));
+        assert!(visitor().run_spanned(rpn("1 / 0")).is_err());
+    }
+}
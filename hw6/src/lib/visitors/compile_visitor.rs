@@ -0,0 +1,223 @@
+use crate::{
+    bytecode::{Compiled, Instruction},
+    error::Error,
+    number::Number,
+    span::{Span, SpanError},
+    token::{Function, Literal, Operator, Token, TokenListVisitor},
+};
+
+/// Compiles RPN to bytecode, checking that every instruction has its operands
+pub struct CompileVisitor<N: Number> {
+    compiled: Compiled<N>,
+    /// Values on the stack after the instructions so far
+    depth: usize,
+    /// Span of the token being compiled
+    span: Span,
+    /// Jumps waiting for the end of their operand or branch,
+    /// with the depth the operand or branch starts at
+    jumps: Vec<(usize, usize)>,
+}
+
+impl<N: Number> CompileVisitor<N> {
+    pub fn new() -> Self {
+        CompileVisitor {
+            compiled: Compiled {
+                code: vec![],
+                spans: vec![],
+                constants: vec![],
+                variables: vec![],
+                target: None,
+                depth: 0,
+            },
+            depth: 0,
+            span: Span::default(),
+            jumps: vec![],
+        }
+    }
+
+    /// Adds `instruction` that takes `operands` values off the stack and pushes one
+    fn emit(&mut self, instruction: Instruction, operands: usize) {
+        self.depth = self.depth - operands + 1;
+        self.compiled.depth = self.compiled.depth.max(self.depth);
+        self.compiled.code.push(instruction);
+        self.compiled.spans.push(self.span);
+    }
+
+    /// Adds a jump that takes `operands` values off the stack, its target is set by `land`
+    fn jump(&mut self, instruction: Instruction, operands: usize) {
+        self.depth -= operands;
+        self.jumps.push((self.compiled.code.len(), self.depth));
+        self.compiled.code.push(instruction);
+        self.compiled.spans.push(self.span);
+    }
+
+    /// Last waiting jump if `is_jump` matches it
+    /// and the operand or branch after it left one value
+    fn landing(&mut self, is_jump: fn(&Instruction) -> bool, token: &str) -> Result<usize, Error> {
+        let unmatched = || Error::invalid(&format!("Unexpected '{}' without its start", token));
+        let (at, depth) = self.jumps.pop().ok_or_else(unmatched)?;
+        if !is_jump(&self.compiled.code[at]) {
+            return Err(unmatched());
+        }
+        if self.depth != depth + 1 {
+            return Err(Error::invalid(&format!(
+                "Expected one value before '{}'",
+                token
+            )));
+        }
+        Ok(at)
+    }
+
+    /// Points the jump at `at` to the next instruction
+    fn land(&mut self, at: usize) {
+        let next = self.compiled.code.len();
+        match &mut self.compiled.code[at] {
+            Instruction::ShortCircuit(_, to)
+            | Instruction::JumpUnless(to)
+            | Instruction::Jump(to) => *to = next,
+            _ => unreachable!("only jumps wait"),
+        }
+    }
+
+    /// Error unless the stack has a value for `token`
+    fn operand(&self, token: &str) -> Result<(), Error> {
+        if self.depth == 0 {
+            return Err(Error::invalid(&format!("Missing operand of '{}'", token)));
+        }
+        Ok(())
+    }
+}
+
+impl<N: Number> Default for CompileVisitor<N> {
+    fn default() -> Self {
+        CompileVisitor::new()
+    }
+}
+
+impl<N: Number> TokenListVisitor for CompileVisitor<N> {
+    type Error = SpanError;
+    type Output = Compiled<N>;
+
+    fn visit_left(&mut self) -> Result<(), Self::Error> {
+        Err(Error::invalid("No parenthesis in RPN expected").into())
+    }
+
+    fn visit_right(&mut self) -> Result<(), Self::Error> {
+        Err(Error::invalid("No parenthesis in RPN expected").into())
+    }
+
+    fn visit_number(&mut self, x: Literal) -> Result<(), Self::Error> {
+        self.compiled.constants.push(N::from_literal(&x)?);
+        self.emit(Instruction::Const(self.compiled.constants.len() - 1), 0);
+        Ok(())
+    }
+
+    /// `?:` only ends the jump over its second branch
+    fn visit_op(&mut self, op: Operator) -> Result<(), Self::Error> {
+        match op {
+            Operator::And | Operator::Or => {
+                let short = |i: &Instruction| matches!(i, Instruction::ShortCircuit(_, _));
+                let at = self.landing(short, &op.to_string())?;
+                self.emit(Instruction::Boolean(op), 1);
+                // the short circuit jumps over the check of the right operand
+                self.land(at);
+                return Ok(());
+            }
+            Operator::Conditional => {
+                let at = self.landing(|i| matches!(i, Instruction::Jump(_)), "?:")?;
+                self.land(at);
+                return Ok(());
+            }
+            _ => {}
+        }
+        if self.depth < op.arity() {
+            return Err(Error::invalid(&format!("Missing operand of '{}'", op)).into());
+        }
+        match op {
+            Operator::UnaryPlus => self.emit(Instruction::Pos, 1),
+            Operator::UnaryMinus => self.emit(Instruction::Neg, 1),
+            Operator::Not => self.emit(Instruction::Not, 1),
+            op => self.emit(Instruction::Binary(op), 2),
+        }
+        Ok(())
+    }
+
+    fn visit_comma(&mut self) -> Result<(), Self::Error> {
+        Err(Error::invalid("No commas in RPN expected").into())
+    }
+
+    fn visit_identifier(&mut self, name: String) -> Result<(), Self::Error> {
+        let variables = &mut self.compiled.variables;
+        let k = match variables.iter().position(|v| *v == name) {
+            Some(k) => k,
+            None => {
+                variables.push(name);
+                variables.len() - 1
+            }
+        };
+        self.emit(Instruction::Load(k), 0);
+        Ok(())
+    }
+
+    fn visit_function(&mut self, function: Function, args: usize) -> Result<(), Self::Error> {
+        function.check_arity(args)?;
+        if self.depth < args {
+            return Err(
+                Error::invalid(&format!("Missing argument of '{}'", function.name())).into(),
+            );
+        }
+        self.emit(Instruction::Call(function, args), args);
+        Ok(())
+    }
+
+    fn visit_equals(&mut self) -> Result<(), Self::Error> {
+        Err(Error::invalid("No '=' in RPN expected").into())
+    }
+
+    fn visit_assign(&mut self, name: String) -> Result<(), Self::Error> {
+        if self.depth == 0 {
+            return Err(Error::invalid(&format!("Missing value of '{}'", name)).into());
+        }
+        self.compiled.target = Some(name);
+        Ok(())
+    }
+
+    fn visit_question(&mut self) -> Result<(), Self::Error> {
+        self.operand("?")?;
+        self.jump(Instruction::JumpUnless(0), 1);
+        Ok(())
+    }
+
+    /// The first branch jumps over the second one, the condition jumps to it
+    fn visit_colon(&mut self) -> Result<(), Self::Error> {
+        let at = self.landing(|i| matches!(i, Instruction::JumpUnless(_)), ":")?;
+        self.jump(Instruction::Jump(0), 1);
+        self.land(at);
+        Ok(())
+    }
+
+    fn visit_short_circuit(&mut self, op: Operator) -> Result<(), Self::Error> {
+        self.operand(&op.to_string())?;
+        self.jump(Instruction::ShortCircuit(op, 0), 1);
+        Ok(())
+    }
+
+    /// Errors are put at the token they happen at
+    fn visit_at(&mut self, token: Token, span: Span) -> Result<(), Self::Error> {
+        self.span = span;
+        self.visit(token).map_err(|e| e.or_at(span))
+    }
+
+    fn get_result(self) -> Result<Self::Output, Self::Error> {
+        if !self.jumps.is_empty() {
+            return Err(
+                Error::invalid("Unexpected end of a short circuit or a conditional").into(),
+            );
+        }
+        match self.depth {
+            1 => Ok(self.compiled),
+            0 => Err(Error::invalid("Unexpected empty evaluation stack").into()),
+            _ => Err(Error::invalid("Unexpected elements left in evaluation stack").into()),
+        }
+    }
+}
@@ -1,3 +1,6 @@
 pub mod parser_visitor;
 pub mod print_visitor;
 pub mod calc_visitor;
+pub mod ast_visitor;
+pub mod compile_visitor;
+pub mod combinators;
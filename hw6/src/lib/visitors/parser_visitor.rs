@@ -1,51 +1,316 @@
-use crate::token::{Operator, Paren, Token, TokenListVisitor};
+use crate::{
+    error::Error,
+    span::{Span, SpanError},
+    token::{Function, Literal, Operator, Paren, Token, TokenListVisitor},
+};
 
 pub struct ParserVisitor {
-    output: Vec<Token>,
-    stack: Vec<Token>,
+    output: Vec<(Token, Span)>,
+    stack: Vec<(Token, Span)>,
+    /// True at the start, after '(', ',' and operators,
+    /// there '+' and '-' are unary
+    operand_expected: bool,
+    /// Number of the current token, from 1
+    position: usize,
+    /// Span of the current token, `None` if spans aren't known
+    span: Option<Span>,
+    /// '(' on the stack
+    open_parens: Vec<OpenParen>,
+    /// Function whose name was the last token and the span of the name, its '(' must come next
+    called: Option<(Function, Option<Span>)>,
+    /// Last variable, a '(' right after it is a call of an unknown function
+    variable: Option<Variable>,
+    /// Variable assigned by the statement, like `x` in `x = 1 + 2`
+    target: Option<(String, Span)>,
 }
 
-type RVoid = Result<(), String>;
-impl TokenListVisitor for ParserVisitor {
-    type Output = Vec<Token>;
-    type Error = String;
+struct OpenParen {
+    position: usize,
+    span: Option<Span>,
+    /// Function called with these parens and the number of commas seen in them
+    call: Option<(Function, usize)>,
+    /// Span of the name of the function
+    name: Option<Span>,
+}
+
+struct Variable {
+    position: usize,
+    name: String,
+    span: Option<Span>,
+}
 
-    fn new() -> ParserVisitor {
+impl ParserVisitor {
+    pub fn new() -> ParserVisitor {
         ParserVisitor {
             output: vec![],
             stack: vec![],
+            operand_expected: true,
+            position: 0,
+            span: None,
+            open_parens: vec![],
+            called: None,
+            variable: None,
+            target: None,
+        }
+    }
+
+    /// RPN of `tokens`, every token keeps its span,
+    /// a function call spans from its name to ')'
+    pub fn parse(
+        tokens: impl IntoIterator<Item = (Token, Span)>,
+    ) -> Result<Vec<(Token, Span)>, SpanError> {
+        let mut parser = ParserVisitor::new();
+        for (token, span) in tokens {
+            parser.visit_at(token, span)?;
+        }
+        parser.finish()
+    }
+
+    /// RPN of `tokens` made as they come, it ends after the first error
+    pub fn stream<I>(tokens: I) -> Rpn<I::IntoIter>
+    where
+        I: IntoIterator<Item = Result<(Token, Span), SpanError>>,
+    {
+        Rpn {
+            tokens: tokens.into_iter(),
+            parser: Some(ParserVisitor::new()),
+            output: vec![].into_iter(),
+        }
+    }
+
+    /// Output that no later token changes, the first token may yet become a target
+    /// and a number right after '-' may yet become negative
+    fn take_ready(&mut self) -> Vec<(Token, Span)> {
+        if self.position < 2 {
+            return vec![];
+        }
+        let mut ready = std::mem::take(&mut self.output);
+        if matches!(
+            (ready.last(), self.stack.last()),
+            (
+                Some((Token::Number(_), _)),
+                Some((Token::Operator(Operator::UnaryMinus), _))
+            )
+        ) {
+            self.output.extend(ready.pop());
+        }
+        ready
+    }
+
+    /// Goes to the next token, only '(' may follow a function name
+    fn step(&mut self) -> RVoid {
+        self.position += 1;
+        match self.called {
+            Some((function, _)) => Err(self.error(Error::ExpectedParen(function))),
+            None => Ok(()),
         }
     }
 
+    /// Span of the current token, empty if spans aren't known
+    fn span(&self) -> Span {
+        self.span.unwrap_or_default()
+    }
+
+    fn error(&self, error: Error) -> SpanError {
+        SpanError {
+            error,
+            span: self.span,
+        }
+    }
+
+    /// Moves the top of the stack into the output.
+    /// '-' of a number makes it a negative literal, so that a number is range checked
+    /// with its sign and `-2147483648` is an i32
+    fn pop_operator(&mut self) {
+        let (token, span) = self.stack.pop().expect("the stack isn't empty");
+        // the operand ends with the last output token, so a number is all of it
+        if let (Token::Operator(Operator::UnaryMinus), Some((Token::Number(x), number))) =
+            (&token, self.output.last_mut())
+        {
+            if !x.is_negative() {
+                *x = -x.clone();
+                *number = span.to(*number);
+                return;
+            }
+        }
+        self.output.push((token, span));
+    }
+
+    /// Moves operators down to the nearest '(' or '?' into the output
+    fn pop_to_paren(&mut self) {
+        while let Some((Token::Operator(_), _)) = self.stack.last() {
+            self.pop_operator();
+        }
+    }
+
+    /// Error of a '?' that is left without ':' by the current token
+    fn check_question(&self) -> RVoid {
+        match self.stack.last() {
+            Some((Token::Question, span)) => Err(SpanError::new(Error::ExpectedColon, *span)),
+            _ => Ok(()),
+        }
+    }
+
+    fn finish(mut self) -> Result<Vec<(Token, Span)>, SpanError> {
+        let at_end = |error| SpanError {
+            error,
+            span: self.span.map(|span| span.after()),
+        };
+        if let Some((function, _)) = self.called {
+            return Err(at_end(Error::ExpectedParen(function)));
+        }
+        if self.operand_expected && self.position > 0 {
+            return Err(at_end(Error::MissingOperand(None)));
+        }
+        if let Some(paren) = self.open_parens.first() {
+            return Err(SpanError {
+                error: Error::UnclosedParen,
+                span: paren.span,
+            });
+        }
+        self.pop_to_paren();
+        self.check_question()?;
+        while !self.stack.is_empty() {
+            self.pop_operator();
+        }
+        if let Some((name, span)) = self.target {
+            self.output.push((Token::Assign(name), span));
+        }
+        Ok(self.output)
+    }
+}
+
+/// RPN of a stream of tokens, see `ParserVisitor::stream`
+pub struct Rpn<I> {
+    tokens: I,
+    /// `None` after the end or an error
+    parser: Option<ParserVisitor>,
+    /// Ready tokens not taken yet
+    output: std::vec::IntoIter<(Token, Span)>,
+}
+
+impl<I: Iterator<Item = Result<(Token, Span), SpanError>>> Iterator for Rpn<I> {
+    type Item = Result<(Token, Span), SpanError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(token) = self.output.next() {
+                return Some(Ok(token));
+            }
+            let parser = self.parser.as_mut()?;
+            let res = match self.tokens.next() {
+                Some(Ok((token, span))) => {
+                    parser.visit_at(token, span).map(|()| parser.take_ready())
+                }
+                Some(Err(e)) => Err(e),
+                None => self.parser.take().expect("parser is there").finish(),
+            };
+            match res {
+                Ok(output) => self.output = output.into_iter(),
+                Err(e) => {
+                    self.parser = None;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+impl Default for ParserVisitor {
+    fn default() -> Self {
+        ParserVisitor::new()
+    }
+}
+
+type RVoid = Result<(), SpanError>;
+impl TokenListVisitor for ParserVisitor {
+    type Output = Vec<Token>;
+    type Error = SpanError;
+
     fn visit_left(&mut self) -> RVoid {
-        self.stack.push(Token::Paren(Paren::Left));
+        let called = self.called.take();
+        self.step()?;
+        if !self.operand_expected {
+            if let Some(variable) = &self.variable {
+                if variable.position + 1 == self.position {
+                    return Err(SpanError {
+                        error: Error::UnknownFunction(variable.name.clone()),
+                        span: variable.span,
+                    });
+                }
+            }
+            return Err(self.error(Error::MissingOperator("(".to_string())));
+        }
+        self.stack.push((Token::Paren(Paren::Left), self.span()));
+        self.open_parens.push(OpenParen {
+            position: self.position,
+            span: self.span,
+            call: called.map(|(function, _)| (function, 0)),
+            name: called.and_then(|(_, name)| name),
+        });
         Ok(())
     }
 
     fn visit_right(&mut self) -> RVoid {
-        while let Some(op) = self.stack.last() {
-            if *op == Token::Paren(Paren::Right) {
-                self.stack.pop();
-                return Ok(());
-            }
-            self.output.push(self.stack.pop().unwrap());
+        self.step()?;
+        // ')' right after the '(' of a call, like f()
+        let empty_call = matches!(
+            self.open_parens.last(),
+            Some(OpenParen { position, call: Some(_), .. }) if *position + 1 == self.position
+        );
+        if self.operand_expected && !empty_call {
+            return Err(self.error(Error::MissingOperand(Some(")".to_string()))));
+        }
+        self.pop_to_paren();
+        self.check_question()?;
+        if self.stack.pop().is_none() {
+            return Err(self.error(Error::UnmatchedParen));
         }
-        return Err("Unexpected ')'".to_string());
+        let paren = self.open_parens.pop().expect("'(' on the stack is open");
+        if let Some((function, commas)) = paren.call {
+            let args = if empty_call { 0 } else { commas + 1 };
+            let call = paren.name.map(|name| name.to(self.span()));
+            function
+                .check_arity(args)
+                .map_err(|error| SpanError { error, span: call })?;
+            self.output
+                .push((Token::Function(function, args), call.unwrap_or_default()));
+        }
+        self.operand_expected = false;
+        Ok(())
     }
 
-    fn visit_number(&mut self, x: u32) -> RVoid {
-        self.output.push(Token::Number(x));
+    fn visit_number(&mut self, x: Literal) -> RVoid {
+        self.step()?;
+        if !self.operand_expected {
+            return Err(self.error(Error::MissingOperator(x.to_string())));
+        }
+        self.operand_expected = false;
+        self.output.push((Token::Number(x), self.span()));
         Ok(())
     }
 
     fn visit_op(&mut self, op: Operator) -> RVoid {
-        while let Some(token) = self.stack.last() {
+        self.step()?;
+        if self.operand_expected {
+            // prefix operators apply to what follows, so nothing is popped for them
+            let unary = op
+                .to_unary()
+                .ok_or_else(|| self.error(Error::MissingOperand(Some(op.to_string()))))?;
+            self.stack.push((Token::Operator(unary), self.span()));
+            return Ok(());
+        }
+        if op.arity() == 1 {
+            return Err(self.error(Error::MissingOperator(op.to_string())));
+        }
+        self.operand_expected = true;
+        while let Some((token, _)) = self.stack.last() {
             match token {
                 Token::Operator(op_on_stack) => {
                     if op.left_associative() && op.precedence() <= op_on_stack.precedence()
                         || !op.left_associative() && op.precedence() < op_on_stack.precedence()
                     {
-                        self.output.push(self.stack.pop().unwrap());
+                        self.pop_operator();
                         continue;
                     }
                     break;
@@ -53,15 +318,126 @@ impl TokenListVisitor for ParserVisitor {
                 _ => break,
             }
         }
-        self.stack.push(Token::Operator(op));
+        // the left operand is done, it may decide the result
+        if matches!(op, Operator::And | Operator::Or) {
+            self.output
+                .push((Token::ShortCircuit(op.clone()), self.span()));
+        }
+        self.stack.push((Token::Operator(op), self.span()));
         Ok(())
     }
 
-    fn get_result(mut self) -> Result<Vec<Token>, String> {
-        while let Some(token) = self.stack.pop() {
-            self.output.push(token);
+    fn visit_comma(&mut self) -> RVoid {
+        self.step()?;
+        if self.operand_expected {
+            return Err(self.error(Error::MissingOperand(Some(",".to_string()))));
+        }
+        match self.open_parens.last_mut() {
+            Some(OpenParen {
+                call: Some((_, commas)),
+                ..
+            }) => *commas += 1,
+            _ => return Err(self.error(Error::UnexpectedComma)),
+        }
+        self.pop_to_paren();
+        self.check_question()?;
+        self.operand_expected = true;
+        Ok(())
+    }
+
+    fn visit_identifier(&mut self, name: String) -> RVoid {
+        self.step()?;
+        if !self.operand_expected {
+            return Err(self.error(Error::MissingOperator(name)));
+        }
+        match Function::from_name(&name) {
+            Some(function) => self.called = Some((function, self.span)),
+            None => {
+                self.output
+                    .push((Token::Identifier(name.clone()), self.span()));
+                self.variable = Some(Variable {
+                    position: self.position,
+                    name,
+                    span: self.span,
+                });
+                self.operand_expected = false;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_function(&mut self, _: Function, _: usize) -> RVoid {
+        self.step()?;
+        Err(self.error(Error::invalid("Unexpected function call in infix input")))
+    }
+
+    /// Only `name = expression` is an assignment
+    fn visit_equals(&mut self) -> RVoid {
+        self.step()?;
+        match self.output.as_slice() {
+            [(Token::Identifier(name), span)] if self.position == 2 => {
+                self.target = Some((name.clone(), *span));
+                self.output.clear();
+                self.operand_expected = true;
+                Ok(())
+            }
+            _ => Err(self.error(Error::UnexpectedEquals)),
         }
-        Ok(self.output)
+    }
+
+    fn visit_assign(&mut self, _: String) -> RVoid {
+        self.step()?;
+        Err(self.error(Error::invalid("Unexpected assignment in infix input")))
+    }
+
+    /// The condition ends, '?' stays on the stack until its ':'
+    fn visit_question(&mut self) -> RVoid {
+        self.step()?;
+        if self.operand_expected {
+            return Err(self.error(Error::MissingOperand(Some("?".to_string()))));
+        }
+        // conditionals are right associative, a '?' after ':' starts the second branch
+        while let Some((Token::Operator(op), _)) = self.stack.last() {
+            if *op == Operator::Conditional {
+                break;
+            }
+            self.pop_operator();
+        }
+        self.output.push((Token::Question, self.span()));
+        self.stack.push((Token::Question, self.span()));
+        self.operand_expected = true;
+        Ok(())
+    }
+
+    /// The first branch ends, '?' on the stack becomes the operator of the conditional
+    fn visit_colon(&mut self) -> RVoid {
+        self.step()?;
+        if self.operand_expected {
+            return Err(self.error(Error::MissingOperand(Some(":".to_string()))));
+        }
+        self.pop_to_paren();
+        match self.stack.last_mut() {
+            Some((token @ Token::Question, _)) => *token = Token::Operator(Operator::Conditional),
+            _ => return Err(self.error(Error::UnmatchedColon)),
+        }
+        self.output.push((Token::Colon, self.span()));
+        self.operand_expected = true;
+        Ok(())
+    }
+
+    fn visit_short_circuit(&mut self, _: Operator) -> RVoid {
+        self.step()?;
+        Err(self.error(Error::invalid("Unexpected short circuit in infix input")))
+    }
+
+    fn visit_at(&mut self, token: Token, span: Span) -> RVoid {
+        self.span = Some(span);
+        self.visit(token)
+    }
+
+    fn get_result(self) -> Result<Vec<Token>, SpanError> {
+        let output = self.finish()?;
+        Ok(output.into_iter().map(|(token, _)| token).collect())
     }
 }
 
@@ -70,6 +446,8 @@ pub(crate) mod test {
     use std::fmt::Debug;
 
     use crate::{
+        error::Error,
+        span::{Span, SpanError},
         token::{Operator, Token, TokenListVisitor},
         tokenizer::Tokenizer,
     };
@@ -78,7 +456,7 @@ pub(crate) mod test {
 
     pub fn check_equal<Visitor>(input: &str, output_expected: Visitor::Output)
     where
-        Visitor: TokenListVisitor,
+        Visitor: TokenListVisitor + Default,
         Visitor::Output: Eq + Debug,
         Visitor::Error: Debug,
     {
@@ -90,7 +468,7 @@ pub(crate) mod test {
 
     pub fn expect_fails<Visitor>(input: &str)
     where
-        Visitor: TokenListVisitor,
+        Visitor: TokenListVisitor + Default,
         Visitor::Output: Debug,
     {
         let tokens = Tokenizer::tokenize(input.to_string()).unwrap();
@@ -117,8 +495,8 @@ pub(crate) mod test {
         check_equal::<ParserVisitor>(
             "2 + 3",
             vec![
-                Token::Number(2),
-                Token::Number(3),
+                Token::Number(2.into()),
+                Token::Number(3.into()),
                 Token::Operator(Operator::Plus),
             ],
         )
@@ -129,14 +507,221 @@ pub(crate) mod test {
         check_equal::<ParserVisitor>(
             "3 - 4 + 5 / 2",
             vec![
-                Token::Number(3),
-                Token::Number(4),
+                Token::Number(3.into()),
+                Token::Number(4.into()),
                 Token::Operator(Operator::Minus),
-                Token::Number(5),
-                Token::Number(2),
+                Token::Number(5.into()),
+                Token::Number(2.into()),
                 Token::Operator(Operator::Slash),
                 Token::Operator(Operator::Plus),
             ],
         )
     }
+
+    fn check_rpn(input: &str, expected: &str) {
+        let tokens = Tokenizer::tokenize(input.to_string()).unwrap();
+        let rpn = ParserVisitor::visit_tokens(tokens)
+            .unwrap()
+            .iter()
+            .map(|token| match token {
+                Token::Number(x) => x.to_string(),
+                Token::Operator(Operator::UnaryMinus) => "neg".to_string(),
+                Token::Operator(Operator::UnaryPlus) => "pos".to_string(),
+                Token::Operator(op) => op.to_string(),
+                Token::Paren(p) => format!("{:?}", p),
+                Token::Function(f, args) => format!("{}:{}", f.name(), args),
+                Token::Identifier(name) => name.clone(),
+                Token::Assign(name) => format!("{} =", name),
+                Token::Question => "?".to_string(),
+                Token::Colon => ":".to_string(),
+                Token::ShortCircuit(op) => format!("{}?", op),
+                Token::Comma | Token::Equals => unreachable!("parser removes them"),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(expected, rpn);
+    }
+
+    #[test]
+    fn test6() {
+        check_rpn("-3 + 4", "-3 4 +");
+        check_rpn("2 * -3", "2 -3 *");
+        check_rpn("- -+2", "2 pos neg neg");
+        check_rpn("-2 ^ 2", "2 2 ^ neg");
+        check_rpn("2 ^ -3 ^ 2", "2 3 2 ^ neg ^");
+        check_rpn("2 ^ 3 ^ 2", "2 3 2 ^ ^");
+        check_rpn("-2 * 3 % 4", "-2 3 * 4 %");
+        check_rpn("-(2) - -x", "-2 x neg -");
+        check_rpn("-2147483648", "-2147483648");
+    }
+
+    #[test]
+    fn test7() {
+        for input in ["* 2", "2 +", "2 3", "2 ^", "% 1"] {
+            expect_fails::<ParserVisitor>(input);
+        }
+    }
+
+    #[test]
+    fn test8() {
+        check_rpn("(1 + 2) * 3", "1 2 + 3 *");
+        check_rpn("-(2 - (3 - 4)) ^ (1 + 1)", "2 3 4 - - 1 1 + ^ neg");
+        check_rpn("((((5))))", "5");
+        check_rpn("2 ^ (-(3))", "2 -3 ^");
+    }
+
+    /// `caret` marks the span of the error in `input`
+    fn expect_error(input: &str, message: &str, caret: &str) {
+        let tokens = Tokenizer::tokenize_spanned(input).unwrap();
+        let error = ParserVisitor::parse(tokens).unwrap_err();
+        assert_eq!(
+            format!("{}\n{}\n{}", message, input, caret),
+            error.render(input)
+        );
+    }
+
+    #[test]
+    fn test9() {
+        expect_error("(1 + 2))", "Unmatched ')'", "       ^");
+        expect_error("1 + (2 * (3 - 4)", "Unclosed '('", "    ^");
+        expect_error("(()", "Missing operand before ')'", "  ^");
+        expect_error("2 (3)", "Missing operator before '('", "  ^");
+        expect_error("(1 +)", "Missing operand before ')'", "    ^");
+    }
+
+    #[test]
+    fn test10() {
+        let depth = 10_000;
+        let input = format!("{}1{}", "(-".repeat(depth), ")".repeat(depth));
+        let rpn = ParserVisitor::visit_tokens(Tokenizer::tokenize(input).unwrap()).unwrap();
+        // the innermost '-' makes -1
+        assert_eq!(depth, rpn.len());
+
+        let input = format!("{}1{}", "(".repeat(depth), ")".repeat(depth - 1));
+        expect_error(&input, "Unclosed '('", "^");
+        let input = format!("{}1 + 2{}", "(".repeat(depth), ")".repeat(depth + 1));
+        let error = ParserVisitor::parse(Tokenizer::tokenize_spanned(&input).unwrap());
+        assert_eq!(
+            Err(SpanError::new(
+                Error::UnmatchedParen,
+                Span::new(2 * depth + 5, 2 * depth + 6)
+            )),
+            error
+        );
+    }
+
+    #[test]
+    fn calls() {
+        check_rpn("max(1, 2 + 3, -4)", "1 2 3 + -4 max:3");
+        check_rpn("2 * abs(-(1 - 3)) ^ 2", "2 1 3 - neg abs:1 2 ^ *");
+        check_rpn("log(2, round(sqrt(8), 1))", "2 8 sqrt:1 1 round:2 log:2");
+        check_rpn("min((1), 2)", "1 2 min:2");
+    }
+
+    #[test]
+    fn call_errors() {
+        let arity = "Function 'log' takes 2 arguments, got 1";
+        expect_error("log(2)", arity, "^^^^^^");
+        let arity = "Function 'abs' takes 1 argument, got 2";
+        expect_error("abs(1, 2)", arity, "^^^^^^^^^");
+        let arity = "Function 'max' takes at least 1 argument, got 0";
+        expect_error("max()", arity, "^^^^^");
+        let arity = "Function 'round' takes 1 to 2 arguments, got 3";
+        expect_error("round(1, 2, 3)", arity, "^^^^^^^^^^^^^^");
+        expect_error("foo(1)", "Unknown function 'foo'", "^^^");
+        expect_error("sin 1", "Expected '(' after 'sin'", "    ^");
+        let comma = "Unexpected ',' outside of function arguments";
+        expect_error("1, 2", comma, " ^");
+        expect_error("max((1, 2))", comma, "      ^");
+        expect_error("max(1,)", "Missing operand before ')'", "      ^");
+        expect_error("max(, 1)", "Missing operand before ','", "    ^");
+        expect_error("2 abs(1)", "Missing operator before 'abs'", "  ^^^");
+        expect_error("2 + sqrt", "Expected '(' after 'sqrt'", "        ^");
+    }
+
+    #[test]
+    fn variables() {
+        check_rpn("rate = 0.07", "0.07 rate =");
+        check_rpn("total = price * (1 + rate)", "price 1 rate + * total =");
+        check_rpn("-x ^ 2 + max(x, pi)", "x 2 ^ neg x pi max:2 +");
+        expect_error("x y", "Missing operator before 'y'", "  ^");
+        expect_error("2 * foo(1)", "Unknown function 'foo'", "    ^^^");
+        let equals = "Only a variable at the start can be assigned, unexpected '='";
+        expect_error("x = y = 1", equals, "      ^");
+        expect_error("(x) = 1", equals, "    ^");
+        expect_error("x =", "Missing operand at the end", "   ^");
+        expect_error("sin = 1", "Expected '(' after 'sin'", "    ^");
+    }
+
+    #[test]
+    fn logic() {
+        check_rpn("a < b + 1 == c", "a b 1 + < c ==");
+        check_rpn("!a && b || c", "a ! &&? b && ||? c ||");
+        check_rpn("a || b && !(c != 1)", "a ||? b &&? c 1 != ! && ||");
+        check_rpn("c ? 1 : 2", "c ? 1 : 2 ?:");
+        check_rpn("a ? b : c ? d : e", "a ? b : c ? d : e ?: ?:");
+        check_rpn("a ? b ? c : d : e", "a ? b ? c : d ?: : e ?:");
+        check_rpn("x = a > 0 ? -a : a", "a 0 > ? a neg : a ?: x =");
+        check_rpn("max(a ? 1 : 2, 3) + 1", "a ? 1 : 2 ?: 3 max:2 1 +");
+        check_rpn("a && b ? c || d : e", "a &&? b && ? c ||? d || : e ?:");
+    }
+
+    #[test]
+    fn logic_errors() {
+        expect_error("1 ? 2", "Expected ':' after '?'", "  ^");
+        expect_error("(1 ? 2) : 3", "Expected ':' after '?'", "   ^");
+        expect_error("max(1 ? 2, 3)", "Expected ':' after '?'", "      ^");
+        expect_error("1 : 2", "Unmatched ':'", "  ^");
+        expect_error("1 ? (2 : 3)", "Unmatched ':'", "       ^");
+        expect_error("1 ? : 2", "Missing operand before ':'", "    ^");
+        expect_error("1 ?", "Missing operand at the end", "   ^");
+        expect_error("2 !", "Missing operator before '!'", "  ^");
+        expect_error("&& 1", "Missing operand before '&&'", "^^");
+    }
+
+    #[test]
+    fn rpn_spans() {
+        let input = "y = -max(2, x) * 3";
+        let rpn = ParserVisitor::parse(Tokenizer::tokenize_spanned(input).unwrap()).unwrap();
+        let spanned: Vec<_> = rpn
+            .iter()
+            .map(|(_, span)| &input[span.start..span.end])
+            .collect();
+        assert_eq!(vec!["2", "x", "max(2, x)", "-", "3", "*", "y"], spanned);
+        let input = "2 * - 5";
+        let rpn = ParserVisitor::parse(Tokenizer::tokenize_spanned(input).unwrap()).unwrap();
+        assert_eq!(Span::new(4, 7), rpn[1].1);
+    }
+
+    #[test]
+    fn stream() {
+        // RPN of an endless input comes as it's read
+        let text = std::iter::repeat("1 + ").flat_map(str::chars);
+        let rpn: Vec<_> = ParserVisitor::stream(Tokenizer::chars(text))
+            .take(5)
+            .map(|token| token.unwrap().0)
+            .collect();
+        assert_eq!(
+            vec![
+                Token::Number(1.into()),
+                Token::Number(1.into()),
+                Token::Operator(Operator::Plus),
+                Token::Number(1.into()),
+                Token::Operator(Operator::Plus),
+            ],
+            rpn
+        );
+
+        let spanned = |input: &str| {
+            let stream = ParserVisitor::stream(Tokenizer::chars(input.chars()));
+            let parsed = ParserVisitor::parse(Tokenizer::tokenize_spanned(input).unwrap());
+            assert_eq!(parsed, stream.collect::<Result<Vec<_>, _>>(), "{}", input);
+        };
+        spanned("x = max(1, -y) ^ 2");
+        spanned("x");
+        spanned("(1 + 2");
+        spanned("f(1)");
+        spanned("-2147483648 - -1");
+        spanned("1 - -2 ^ -3");
+    }
 }
@@ -1,57 +1,312 @@
-use crate::token::{Operator, TokenListVisitor};
+use crate::{
+    error::Error,
+    token::{Function, Literal, Operator, TokenListVisitor},
+};
 
-pub struct PrintVisitor;
+/// Order the printer puts the operators and their operands in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Notation {
+    /// Operators after their operands, like the input of the visitor
+    #[default]
+    Rpn,
+    /// Operators before their operands, spelled like the RPN
+    Prefix,
+    /// Every operator in parentheses with its operands
+    Infix,
+}
 
-impl TokenListVisitor for PrintVisitor {
-    type Error = &'static str;
-    type Output = ();
+/// Prints RPN to a string in the notation, with the separator between the tokens
+pub struct PrintVisitor {
+    notation: Notation,
+    separator: String,
+    /// Operators spelled other than the default
+    spellings: Vec<(Operator, String)>,
+    /// Printed operands, the same as the values the RPN would leave on the stack
+    stack: Vec<String>,
+}
+
+impl PrintVisitor {
+    /// RPN with the tokens separated by spaces
+    pub fn new() -> Self {
+        PrintVisitor::with_notation(Notation::Rpn)
+    }
+
+    pub fn with_notation(notation: Notation) -> Self {
+        PrintVisitor {
+            notation,
+            separator: " ".to_string(),
+            spellings: vec![],
+            stack: vec![],
+        }
+    }
 
-    fn new() -> Self {
-        PrintVisitor
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+        self
     }
 
+    /// Prints `op` as `s`, like × for `*`
+    pub fn spell(mut self, op: Operator, s: &str) -> Self {
+        self.spellings.retain(|(o, _)| *o != op);
+        self.spellings.push((op, s.to_string()));
+        self
+    }
+
+    /// Unary operators are neg and pos, unless they are in infix
+    fn spelling(&self, op: &Operator) -> String {
+        if let Some((_, s)) = self.spellings.iter().find(|(o, _)| o == op) {
+            return s.clone();
+        }
+        match op {
+            Operator::UnaryMinus if self.notation != Notation::Infix => "neg".to_string(),
+            Operator::UnaryPlus if self.notation != Notation::Infix => "pos".to_string(),
+            op => op.to_string(),
+        }
+    }
+
+    fn pop(&mut self, missing: impl FnOnce() -> String) -> Result<String, Error> {
+        self.stack.pop().ok_or_else(|| Error::invalid(&missing()))
+    }
+
+    /// Pushes the operator or function `label` applied to the `operands`
+    /// in RPN or prefix notation
+    fn push_node(&mut self, label: String, operands: Vec<String>) {
+        let mut parts = operands;
+        match self.notation {
+            Notation::Prefix => parts.insert(0, label),
+            _ => parts.push(label),
+        }
+        self.stack.push(parts.join(&self.separator));
+    }
+}
+
+impl Default for PrintVisitor {
+    fn default() -> Self {
+        PrintVisitor::new()
+    }
+}
+
+impl TokenListVisitor for PrintVisitor {
+    type Error = Error;
+    type Output = String;
+
     fn visit_left(&mut self) -> Result<(), Self::Error> {
-        Err("There shouldn't be parenthesis in RPN")
+        Err(Error::invalid("There shouldn't be parenthesis in RPN"))
     }
 
     fn visit_right(&mut self) -> Result<(), Self::Error> {
-        Err("There shouldn't be parenthesis in RPN")
+        Err(Error::invalid("There shouldn't be parenthesis in RPN"))
+    }
+
+    /// Negative literals are printed with unary minus, like they were written
+    fn visit_number(&mut self, x: Literal) -> Result<(), Self::Error> {
+        let negative = x.is_negative();
+        self.stack.push(x.abs().to_string());
+        if negative {
+            self.visit_op(Operator::UnaryMinus)?;
+        }
+        Ok(())
+    }
+
+    fn visit_op(&mut self, op: Operator) -> Result<(), Self::Error> {
+        let missing = || format!("Missing operand of '{}'", op);
+        let x2 = self.pop(missing)?;
+        let s = self.spelling(&op);
+        match op.arity() {
+            1 => match self.notation {
+                Notation::Infix => self.stack.push(format!("({}{})", s, x2)),
+                _ => self.push_node(s, vec![x2]),
+            },
+            2 => {
+                let x1 = self.pop(missing)?;
+                match self.notation {
+                    Notation::Infix => {
+                        let sep = &self.separator;
+                        self.stack
+                            .push(format!("({}{}{}{}{})", x1, sep, s, sep, x2));
+                    }
+                    _ => self.push_node(s, vec![x1, x2]),
+                }
+            }
+            _ => {
+                let x1 = self.pop(missing)?;
+                let c = self.pop(missing)?;
+                match self.notation {
+                    Notation::Infix => {
+                        let sep = &self.separator;
+                        self.stack
+                            .push(format!("({}{}?{}{}{}:{}{})", c, sep, sep, x1, sep, sep, x2));
+                    }
+                    _ => self.push_node(s, vec![c, x1, x2]),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_comma(&mut self) -> Result<(), Self::Error> {
+        Err(Error::invalid("There shouldn't be commas in RPN"))
     }
 
-    fn visit_number(&mut self, x: u32) -> Result<(), Self::Error> {
-        print!("{} ", x);
+    fn visit_identifier(&mut self, name: String) -> Result<(), Self::Error> {
+        self.stack.push(name);
         Ok(())
     }
 
-    fn visit_op(&mut self, op: crate::token::Operator) -> Result<(), Self::Error> {
-        let s = match op {
-            Operator::Plus => "+",
-            Operator::Minus => "-",
-            Operator::Star => "*",
-            Operator::Slash => "/",
+    /// Functions with any number of arguments are printed with it, like max:3,
+    /// except in infix
+    fn visit_function(&mut self, function: Function, args: usize) -> Result<(), Self::Error> {
+        if self.stack.len() < args {
+            return Err(Error::invalid(&format!(
+                "Missing argument of '{}'",
+                function.name()
+            )));
+        }
+        let args = self.stack.split_off(self.stack.len() - args);
+        match (self.notation, function.arity()) {
+            (Notation::Infix, _) => {
+                let args = args.join(&format!(",{}", self.separator));
+                self.stack.push(format!("{}({})", function.name(), args));
+            }
+            (_, (_, None)) => self.push_node(format!("{}:{}", function.name(), args.len()), args),
+            _ => self.push_node(function.name().to_string(), args),
+        }
+        Ok(())
+    }
+
+    fn visit_equals(&mut self) -> Result<(), Self::Error> {
+        Err(Error::invalid("There shouldn't be '=' in RPN"))
+    }
+
+    fn visit_assign(&mut self, name: String) -> Result<(), Self::Error> {
+        let x = self.pop(|| format!("Missing value of '{}'", name))?;
+        let sep = &self.separator;
+        let s = match self.notation {
+            Notation::Rpn => format!("{}{}{}{}=", x, sep, name, sep),
+            Notation::Prefix => format!("={}{}{}{}", sep, name, sep, x),
+            Notation::Infix => format!("{}{}={}{}", name, sep, sep, x),
         };
-        print!("{} ", s);
+        self.stack.push(s);
+        Ok(())
+    }
+
+    /// The markers of short circuits and conditionals aren't printed
+    fn visit_question(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn visit_colon(&mut self) -> Result<(), Self::Error> {
         Ok(())
     }
 
-    fn get_result(self) -> Result<(), Self::Error> {
-        println!();
+    fn visit_short_circuit(&mut self, _: Operator) -> Result<(), Self::Error> {
         Ok(())
     }
+
+    fn get_result(mut self) -> Result<Self::Output, Self::Error> {
+        let res = self.pop(|| "Unexpected empty expression stack".to_string())?;
+        if !self.stack.is_empty() {
+            return Err(Error::invalid(
+                "Unexpected elements left in expression stack",
+            ));
+        }
+        Ok(res)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
-        token::TokenListVisitor, tokenizer::Tokenizer, visitors::parser_visitor::ParserVisitor,
+        token::{Operator, TokenListVisitor},
+        tokenizer::Tokenizer,
+        visitors::parser_visitor::ParserVisitor,
     };
 
-    use super::PrintVisitor;
+    use super::{Notation, PrintVisitor};
+
+    fn print(printer: PrintVisitor, input: &str) -> String {
+        let tokens = Tokenizer::tokenize_spanned(input).unwrap();
+        printer
+            .run_spanned(ParserVisitor::parse(tokens).unwrap())
+            .unwrap()
+    }
 
     #[test]
     fn test() {
         let tokens = Tokenizer::tokenize("1 + 2 * 3 - 8".to_string()).unwrap();
         let tokens = ParserVisitor::visit_tokens(tokens).unwrap();
-        PrintVisitor::visit_tokens(tokens).unwrap();
+        assert_eq!(
+            Ok("1 2 3 * + 8 -".to_string()),
+            PrintVisitor::visit_tokens(tokens)
+        );
+    }
+
+    #[test]
+    fn notations() {
+        let input = "y = -x ^ 2 * max(1, 2, x) / log(2, 8)";
+        assert_eq!(
+            "x 2 ^ neg 1 2 x max:3 * 2 8 log / y =",
+            print(PrintVisitor::new(), input)
+        );
+        assert_eq!(
+            "= y / * neg ^ x 2 max:3 1 2 x log 2 8",
+            print(PrintVisitor::with_notation(Notation::Prefix), input)
+        );
+        assert_eq!(
+            "y = (((-(x ^ 2)) * max(1, 2, x)) / log(2, 8))",
+            print(PrintVisitor::with_notation(Notation::Infix), input)
+        );
+        // the same as the tree
+        let tree = crate::visitors::ast_visitor::test::tree(input);
+        assert_eq!(
+            tree.prefix(),
+            print(PrintVisitor::with_notation(Notation::Prefix), input)
+        );
+    }
+
+    #[test]
+    fn spelling() {
+        let printer = || {
+            PrintVisitor::with_notation(Notation::Infix)
+                .separator("")
+                .spell(Operator::Star, "×")
+                .spell(Operator::Slash, "÷")
+        };
+        assert_eq!("((2×3)÷(-4))", print(printer(), "2 * 3 / -4"));
+        assert_eq!("max(1,2)", print(printer(), "max(1, 2)"));
+        let printer = PrintVisitor::new()
+            .separator(", ")
+            .spell(Operator::UnaryMinus, "~");
+        assert_eq!("1, ~, 2, +", print(printer, "-1 + 2"));
+    }
+
+    #[test]
+    fn logic() {
+        let input = "a > 0 && !b ? -a : a == 1";
+        assert_eq!(
+            "a 0 > b ! && a neg a 1 == ?:",
+            print(PrintVisitor::new(), input)
+        );
+        assert_eq!(
+            "?: && > a 0 ! b neg a == a 1",
+            print(PrintVisitor::with_notation(Notation::Prefix), input)
+        );
+        assert_eq!(
+            "(((a > 0) && (!b)) ? (-a) : (a == 1))",
+            print(PrintVisitor::with_notation(Notation::Infix), input)
+        );
+        let printer = PrintVisitor::with_notation(Notation::Infix)
+            .spell(Operator::And, "∧")
+            .spell(Operator::LessEqual, "≤");
+        assert_eq!("((a ≤ 1) ∧ b)", print(printer, "a <= 1 && b"));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(PrintVisitor::visit_tokens(vec![]).is_err());
+        for input in ["1 2", "+", "max(1, 2) 3"] {
+            let tokens = Tokenizer::tokenize(input.to_string()).unwrap();
+            assert!(PrintVisitor::visit_tokens(tokens).is_err());
+        }
     }
 }